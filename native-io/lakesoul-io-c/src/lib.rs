@@ -25,17 +25,18 @@ use std::sync::Arc;
 
 pub use arrow::array::{export_array_into_raw, StructArray};
 use arrow::array::{make_array_from_raw, Array};
-use arrow::datatypes::Schema;
+use arrow::datatypes::{Field, Schema};
 use arrow::error::ArrowError::CastError;
 pub use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
 
-use lakesoul_io::lakesoul_io_config::{LakeSoulIOConfig, LakeSoulIOConfigBuilder};
+use lakesoul_io::lakesoul_io_config::{CdcReadMode, FileSplit, LakeSoulIOConfig, LakeSoulIOConfigBuilder};
 use tokio::runtime::{Builder, Runtime};
 
 use lakesoul_io::lakesoul_reader::{
     ArrowResult, DataFusionError, LakeSoulReader, RecordBatch, SyncSendableMutableLakeSoulReader,
 };
-use lakesoul_io::lakesoul_writer::SyncSendableMutableLakeSoulWriter;
+use lakesoul_io::lakesoul_writer::{SyncSendableMutableLakeSoulWriter, WrittenFile};
+use serde_json::json;
 
 #[repr(C)]
 pub struct Result<OpaqueT> {
@@ -51,6 +52,14 @@ impl<OpaqueT> Result<OpaqueT> {
         }
     }
 
+    // keep the object usable even if an operation on it failed
+    pub fn new_with_error<T>(obj: T, err_msg: &str) -> Self {
+        Result {
+            ptr: convert_to_opaque_raw::<T, OpaqueT>(obj),
+            err: CString::new(err_msg).unwrap().into_raw(),
+        }
+    }
+
     pub fn error(err_msg: &str) -> Self {
         Result {
             ptr: std::ptr::null_mut(),
@@ -147,6 +156,9 @@ pub extern "C" fn lakesoul_config_builder_add_single_aux_sort_column(
     }
 }
 
+/// Deprecated, use `lakesoul_config_builder_add_filter_checked` to get the error here.
+/// An invalid filter is kept as an error of the builder, which fails creating the reader
+/// and is returned by `check_reader_created`
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_add_filter(
     builder: NonNull<IOConfigBuilder>,
@@ -154,7 +166,119 @@ pub extern "C" fn lakesoul_config_builder_add_filter(
 ) -> NonNull<IOConfigBuilder> {
     unsafe {
         let filter = CStr::from_ptr(filter).to_str().unwrap().to_string();
-        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_filter_str(filter))
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        builder.add_filter_str_deferred(filter);
+        convert_to_opaque(builder)
+    }
+}
+
+/// The builder is kept in the result even if the filter is invalid,
+/// use `check_config_builder_result` to get the error
+/// and `take_config_builder_from_result` to continue building
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_filter_checked(
+    builder: NonNull<IOConfigBuilder>,
+    filter: *const c_char,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let filter = CStr::from_ptr(filter).to_str().unwrap().to_string();
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.add_filter_str(filter);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+/// Add a SQL boolean expression as filter,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_sql_filter(
+    builder: NonNull<IOConfigBuilder>,
+    sql: *const c_char,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let sql = CStr::from_ptr(sql).to_str().unwrap();
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.add_sql_filter(sql);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+/// Add a column computed by a SQL expression and named by alias,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_sql_projection(
+    builder: NonNull<IOConfigBuilder>,
+    sql: *const c_char,
+    alias: *const c_char,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let sql = CStr::from_ptr(sql).to_str().unwrap();
+        let alias = CStr::from_ptr(alias).to_str().unwrap().to_string();
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.add_sql_projection(sql, alias);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+/// Add predicates from a serialized Substrait `ExtendedExpression` of `len` bytes,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_substrait_filter(
+    builder: NonNull<IOConfigBuilder>,
+    extended_expression: *const u8,
+    len: c_size_t,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let bytes = slice::from_raw_parts(extended_expression, len);
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.add_substrait_filter(bytes);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+/// Select columns referenced by a serialized Substrait `ExtendedExpression` of `len` bytes,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_substrait_projection(
+    builder: NonNull<IOConfigBuilder>,
+    extended_expression: *const u8,
+    len: c_size_t,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let bytes = slice::from_raw_parts(extended_expression, len);
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.add_substrait_projection(bytes);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+// result keeping the builder, with the error of the operation on it if any
+fn config_builder_result(
+    builder: LakeSoulIOConfigBuilder,
+    result: std::result::Result<(), DataFusionError>,
+) -> Result<IOConfigBuilder> {
+    match result {
+        Ok(()) => Result::<IOConfigBuilder>::new(builder),
+        Err(e) => Result::<IOConfigBuilder>::new_with_error(builder, format!("{}", e).as_str()),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn check_config_builder_result(result: NonNull<Result<IOConfigBuilder>>) -> *const c_char {
+    unsafe { result.as_ref().err }
+}
+
+/// Free the result and its error, and return the builder in it
+#[no_mangle]
+pub extern "C" fn take_config_builder_from_result(
+    result: NonNull<Result<IOConfigBuilder>>,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let result = *Box::from_raw(result.as_ptr());
+        if !result.err.is_null() {
+            drop(CString::from_raw(result.err as *mut c_char));
+        }
+        NonNull::new_unchecked(result.ptr)
     }
 }
 
@@ -172,6 +296,136 @@ pub extern "C" fn lakesoul_config_builder_set_schema(
     }
 }
 
+/// Only read rows of primary keys in an Arrow array, a struct array with a field
+/// named by each primary key or an array of the only primary key named by it.
+/// The result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_lookup_keys(
+    builder: NonNull<IOConfigBuilder>,
+    schema_addr: c_ptrdiff_t,
+    array_addr: c_ptrdiff_t,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let keys = (|| {
+            let ffi_schema = schema_addr as *mut FFI_ArrowSchema;
+            let field = Field::try_from(&*ffi_schema)?;
+            let array = make_array_from_raw(array_addr as *mut FFI_ArrowArray, ffi_schema)?;
+            match array.as_any().downcast_ref::<StructArray>() {
+                Some(struct_array) => Ok(RecordBatch::from(struct_array)),
+                None => RecordBatch::try_from_iter([(field.name().as_str(), array)]),
+            }
+        })();
+        let result = match keys {
+            Ok(keys) => Result::<IOConfigBuilder>::new(builder.with_lookup_keys(keys)),
+            Err(e) => Result::<IOConfigBuilder>::new_with_error(builder, format!("{}", e).as_str()),
+        };
+        convert_to_nonnull(result)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_default_column_value(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    value: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let value = CStr::from_ptr(value).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_default_column_value(column, value),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_partition_value(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    value: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let value = CStr::from_ptr(value).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_partition_value(column, value),
+        )
+    }
+}
+
+/// `partition_desc` is like `date=2023-01-01,region=cn`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_partition_desc(
+    builder: NonNull<IOConfigBuilder>,
+    partition_desc: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let partition_desc = CStr::from_ptr(partition_desc).to_str().unwrap();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_partition_desc(partition_desc),
+        )
+    }
+}
+
+/// read row groups whose midpoint lies in `[start, start + length)` bytes of the file
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_file_byte_range(
+    builder: NonNull<IOConfigBuilder>,
+    file: *const c_char,
+    start: i64,
+    length: i64,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let file = CStr::from_ptr(file).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_file_split(file, FileSplit::ByteRange { start, length }),
+        )
+    }
+}
+
+/// read row groups of the given indices of the file
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_file_row_groups(
+    builder: NonNull<IOConfigBuilder>,
+    file: *const c_char,
+    row_groups: *const c_size_t,
+    row_group_num: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let file = CStr::from_ptr(file).to_str().unwrap().to_string();
+        let row_groups = slice::from_raw_parts(row_groups, row_group_num).to_vec();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_file_split(file, FileSplit::RowGroups(row_groups)),
+        )
+    }
+}
+
+/// `exist_cols` is comma separated column names written in the file,
+/// same as `file_exist_cols` of LakeSoul's data file meta
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_file_exist_cols(
+    builder: NonNull<IOConfigBuilder>,
+    file: *const c_char,
+    exist_cols: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let file = CStr::from_ptr(file).to_str().unwrap().to_string();
+        let exist_cols = CStr::from_ptr(exist_cols)
+            .to_str()
+            .unwrap()
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_string())
+            .collect();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_file_exist_cols(file, exist_cols),
+        )
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_thread_num(
     builder: NonNull<IOConfigBuilder>,
@@ -188,6 +442,14 @@ pub extern "C" fn lakesoul_config_builder_set_batch_size(
     convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_batch_size(batch_size))
 }
 
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_limit(
+    builder: NonNull<IOConfigBuilder>,
+    limit: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_limit(limit))
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_max_row_group_size(
     builder: NonNull<IOConfigBuilder>,
@@ -198,6 +460,174 @@ pub extern "C" fn lakesoul_config_builder_set_max_row_group_size(
     )
 }
 
+// roll to the next file once bytes of row groups written reach max file size
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_max_file_size(
+    builder: NonNull<IOConfigBuilder>,
+    max_file_size: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_max_file_size(max_file_size as u64),
+    )
+}
+
+/// Compression codec of written files like `zstd`, default to snappy,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_compression(
+    builder: NonNull<IOConfigBuilder>,
+    codec: *const c_char,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let codec = CStr::from_ptr(codec).to_str().unwrap().to_string();
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.set_compression(codec);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+/// Compression codec of a column overriding the codec of files,
+/// the result is handled the same as `lakesoul_config_builder_add_filter_checked`
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_column_compression(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    codec: *const c_char,
+) -> NonNull<Result<IOConfigBuilder>> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let codec = CStr::from_ptr(codec).to_str().unwrap().to_string();
+        let mut builder = from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder);
+        let result = builder.set_column_compression(column, codec);
+        convert_to_nonnull(config_builder_result(builder, result))
+    }
+}
+
+// dictionary encoding of written files, default to true
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_dictionary_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_dictionary_enabled(enabled))
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_column_dictionary_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_column_dictionary_enabled(column, enabled),
+        )
+    }
+}
+
+// encoding of values not dictionary encoded like `delta_binary_packed`, default to plain
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_encoding(
+    builder: NonNull<IOConfigBuilder>,
+    encoding: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let encoding = CStr::from_ptr(encoding).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_encoding(encoding))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_column_encoding(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    encoding: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let encoding = CStr::from_ptr(encoding).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_column_encoding(column, encoding),
+        )
+    }
+}
+
+// statistics written in files, `none`, `chunk` or `page`, default to page
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_statistics_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    level: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let level = CStr::from_ptr(level).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_statistics_enabled(level),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_column_statistics_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    level: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let level = CStr::from_ptr(level).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_column_statistics_enabled(column, level),
+        )
+    }
+}
+
+// limits of bytes of data pages and dictionary pages, default to 1MB
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_data_page_size(
+    builder: NonNull<IOConfigBuilder>,
+    data_page_size: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_data_page_size(data_page_size),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_dictionary_page_size(
+    builder: NonNull<IOConfigBuilder>,
+    dictionary_page_size: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+            .with_dictionary_page_size(dictionary_page_size),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_created_by(
+    builder: NonNull<IOConfigBuilder>,
+    created_by: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let created_by = CStr::from_ptr(created_by).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_created_by(created_by))
+    }
+}
+
+// roll to the next file once max file rows are written
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_max_file_rows(
+    builder: NonNull<IOConfigBuilder>,
+    max_file_rows: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_max_file_rows(max_file_rows),
+    )
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_buffer_size(
     builder: NonNull<IOConfigBuilder>,
@@ -268,6 +698,135 @@ pub extern "C" fn lakesoul_config_builder_add_primary_keys(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_merge_op(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    merge_op: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let merge_op = CStr::from_ptr(merge_op).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_merge_op(column, merge_op),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_cdc_column(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_cdc_column(column))
+    }
+}
+
+// read full changelog of CDC tables instead of merged rows
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_cdc_changelog(
+    builder: NonNull<IOConfigBuilder>,
+    changelog: bool,
+) -> NonNull<IOConfigBuilder> {
+    let mode = if changelog {
+        CdcReadMode::Changelog
+    } else {
+        CdcReadMode::Merged
+    };
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_cdc_read_mode(mode))
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_hash_bucket_num(
+    builder: NonNull<IOConfigBuilder>,
+    hash_bucket_num: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_hash_bucket_num(hash_bucket_num),
+    )
+}
+
+// hash bucket of all files read, exposed in metadata of reader schema
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_hash_bucket_id(
+    builder: NonNull<IOConfigBuilder>,
+    hash_bucket_id: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_hash_bucket_id(hash_bucket_id),
+    )
+}
+
+// emit batches of tables without primary keys in file order, default to true
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_keep_file_order(
+    builder: NonNull<IOConfigBuilder>,
+    keep_file_order: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_keep_file_order(keep_file_order),
+    )
+}
+
+// skip row groups by parquet bloom filters, default to true
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_bloom_filter_pruning(
+    builder: NonNull<IOConfigBuilder>,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_bloom_filter_pruning(enabled),
+    )
+}
+
+// skip pages by parquet page indexes, default to true
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_page_index_pruning(
+    builder: NonNull<IOConfigBuilder>,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_page_index_pruning(enabled))
+}
+
+// range partition columns of written files, in the order of partition dirs
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_single_range_partition(
+    builder: NonNull<IOConfigBuilder>,
+    col: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let col = CStr::from_ptr(col).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_range_partition(col))
+    }
+}
+
+// write files of partitions and buckets under the table path
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_table_path(
+    builder: NonNull<IOConfigBuilder>,
+    table_path: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let table_path = CStr::from_ptr(table_path).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_table_path(table_path))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_file_name_prefix(
+    builder: NonNull<IOConfigBuilder>,
+    prefix: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let prefix = CStr::from_ptr(prefix).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_file_name_prefix(prefix),
+        )
+    }
+}
+
 // C interface for reader
 
 #[no_mangle]
@@ -386,6 +945,24 @@ pub extern "C" fn lakesoul_reader_get_schema(reader: NonNull<Result<Reader>>, sc
     }
 }
 
+// number of row groups skipped by filters so far
+#[no_mangle]
+pub extern "C" fn lakesoul_reader_get_pruned_row_groups(reader: NonNull<Result<Reader>>) -> c_size_t {
+    unsafe {
+        let reader = NonNull::new_unchecked(reader.as_ref().ptr as *mut SyncSendableMutableLakeSoulReader);
+        reader.as_ref().get_pruning_metrics().row_groups_pruned()
+    }
+}
+
+// number of pages skipped by filters so far
+#[no_mangle]
+pub extern "C" fn lakesoul_reader_get_pruned_pages(reader: NonNull<Result<Reader>>) -> c_size_t {
+    unsafe {
+        let reader = NonNull::new_unchecked(reader.as_ref().ptr as *mut SyncSendableMutableLakeSoulReader);
+        reader.as_ref().get_pruning_metrics().pages_pruned()
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn free_lakesoul_reader(mut reader: NonNull<Result<Reader>>) {
     unsafe {
@@ -472,6 +1049,57 @@ pub extern "C" fn flush_and_close_writer(writer: NonNull<Result<Writer>>, callba
     }
 }
 
+// consumes the writer pointer like flush_and_close_writer,
+// and passes json of the written files to callback instead of the error if succeeded
+#[no_mangle]
+pub extern "C" fn flush_and_close_writer_with_files(writer: NonNull<Result<Writer>>, callback: ResultCallback) {
+    unsafe {
+        let writer =
+            from_opaque::<Writer, SyncSendableMutableLakeSoulWriter>(NonNull::new_unchecked(writer.as_ref().ptr));
+        match writer.flush_and_close() {
+            Ok(files) => {
+                let files = CString::new(written_files_json(&files)).unwrap();
+                call_result_callback(callback, true, files.as_ptr())
+            }
+            Err(e) => call_result_callback(
+                callback,
+                false,
+                CString::new(format!("{}", e).as_str()).unwrap().into_raw(),
+            ),
+        }
+    }
+}
+
+fn written_files_json(files: &[WrittenFile]) -> String {
+    let files = files
+        .iter()
+        .map(|file| {
+            let column_statistics = file
+                .column_statistics
+                .iter()
+                .map(|stats| {
+                    json!({
+                        "column": stats.column,
+                        "min": stats.min,
+                        "max": stats.max,
+                        "null_count": stats.null_count,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "path": file.path,
+                "partition_desc": file.partition_desc,
+                "size": file.size,
+                "num_rows": file.num_rows,
+                "num_row_groups": file.num_row_groups,
+                "columns": file.columns,
+                "column_statistics": column_statistics,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::Value::Array(files).to_string()
+}
+
 // C interface for tokio::runtime
 
 // opaque types to pass as raw pointers
@@ -527,10 +1155,12 @@ mod tests {
         lakesoul_config_builder_add_single_primary_key, lakesoul_config_builder_set_batch_size,
         lakesoul_config_builder_set_max_row_group_size, lakesoul_config_builder_set_object_store_option,
         lakesoul_config_builder_set_schema, lakesoul_config_builder_set_thread_num, lakesoul_reader_get_schema,
-        next_record_batch, start_reader, tokio_runtime_builder_set_thread_num, write_record_batch, IOConfigBuilder,
+        next_record_batch, start_reader, tokio_runtime_builder_set_thread_num, write_record_batch, written_files_json,
+        IOConfigBuilder,
     };
     use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
     use core::ffi::c_ptrdiff_t;
+    use lakesoul_io::lakesoul_writer::{ColumnStatistics, WrittenFile};
     use std::ffi::{CStr, CString};
     use std::os::raw::c_char;
     use std::ptr::NonNull;
@@ -732,6 +1362,30 @@ mod tests {
         free_lakesoul_reader(reader);
     }
 
+    #[test]
+    fn test_written_files_json() {
+        let file = WrittenFile {
+            path: "/t/part-00000.parquet".to_string(),
+            partition_desc: "-5".to_string(),
+            size: 100,
+            num_rows: 3,
+            num_row_groups: 1,
+            columns: vec!["id".to_string()],
+            column_statistics: vec![ColumnStatistics {
+                column: "id".to_string(),
+                min: Some("1".to_string()),
+                max: Some("3".to_string()),
+                null_count: Some(0),
+            }],
+        };
+        let value: serde_json::Value = serde_json::from_str(&written_files_json(&[file])).unwrap();
+        assert_eq!(value[0]["path"], "/t/part-00000.parquet");
+        assert_eq!(value[0]["num_rows"], 3);
+        assert_eq!(value[0]["columns"][0], "id");
+        assert_eq!(value[0]["column_statistics"][0]["max"], "3");
+        assert_eq!(value[0]["column_statistics"][0]["null_count"], 0);
+    }
+
     #[test]
     fn test_native_read_sort_write() {
         let mut reader_config_builder = crate::new_lakesoul_io_config_builder();
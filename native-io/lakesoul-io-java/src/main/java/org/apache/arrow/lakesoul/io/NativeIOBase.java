@@ -27,6 +27,7 @@ import org.apache.arrow.lakesoul.memory.ArrowMemoryUtils;
 import org.apache.arrow.memory.BufferAllocator;
 import org.apache.arrow.vector.types.pojo.Schema;
 
+import java.io.IOException;
 import java.util.function.BiConsumer;
 
 public class NativeIOBase implements AutoCloseable {
@@ -75,6 +76,13 @@ public class NativeIOBase implements AutoCloseable {
         ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_single_column(ioConfigBuilder, columnPtr);
     }
 
+    public void setPrimaryKeys(Iterable<String> primaryKeys) {
+        for (String pk : primaryKeys) {
+            Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, pk);
+            ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_single_primary_key(ioConfigBuilder, ptr);
+        }
+    }
+
     public void setSchema(Schema schema) {
         assert ioConfigBuilder != null;
         ArrowSchema ffiSchema = ArrowSchema.allocateNew(allocator);
@@ -97,6 +105,12 @@ public class NativeIOBase implements AutoCloseable {
         ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_batch_size(ioConfigBuilder, batchSize);
     }
 
+    // writers given as many files as buckets route rows to files of their hash buckets
+    public void setHashBucketNum(int hashBucketNum) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_hash_bucket_num(ioConfigBuilder, hashBucketNum);
+    }
+
     public void setBufferSize(int bufferSize) {
         assert ioConfigBuilder != null;
         ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_buffer_size(ioConfigBuilder, bufferSize);
@@ -119,6 +133,15 @@ public class NativeIOBase implements AutoCloseable {
         }
     }
 
+    protected void takeConfigBuilderResult(Pointer result) throws IOException {
+        Pointer err = libLakeSoulIO.check_config_builder_result(result);
+        String errMsg = err == null ? null : err.getString(0);
+        ioConfigBuilder = libLakeSoulIO.take_config_builder_from_result(result);
+        if (errMsg != null) {
+            throw new IOException(errMsg);
+        }
+    }
+
     @Override
     public void close() throws Exception {
         if (provider != null) {
@@ -17,9 +17,11 @@
 package org.apache.arrow.lakesoul.io;
 
 import jnr.ffi.Pointer;
+import org.apache.arrow.c.ArrowArray;
 import org.apache.arrow.c.ArrowSchema;
 import org.apache.arrow.c.Data;
 import org.apache.arrow.lakesoul.io.jnr.LibLakeSoulIO;
+import org.apache.arrow.vector.VectorSchemaRoot;
 import org.apache.arrow.vector.types.pojo.Schema;
 
 import java.io.IOException;
@@ -57,10 +59,126 @@ public class NativeIOReader extends NativeIOBase implements AutoCloseable {
         }
     }
 
-    public void addFilter(String filter) {
+    public void addFilter(String filter) throws IOException {
         assert ioConfigBuilder != null;
         Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, filter);
-        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_filter(ioConfigBuilder, ptr);
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_add_filter_checked(ioConfigBuilder, ptr));
+    }
+
+    public void addSqlFilter(String sql) throws IOException {
+        assert ioConfigBuilder != null;
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, sql);
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_add_sql_filter(ioConfigBuilder, ptr));
+    }
+
+    public void addSqlProjection(String sql, String alias) throws IOException {
+        assert ioConfigBuilder != null;
+        Pointer sqlPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, sql);
+        Pointer aliasPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, alias);
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_add_sql_projection(ioConfigBuilder, sqlPtr, aliasPtr));
+    }
+
+    public void addSubstraitFilter(byte[] extendedExpression) throws IOException {
+        assert ioConfigBuilder != null;
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_add_substrait_filter(
+                ioConfigBuilder, extendedExpression, extendedExpression.length));
+    }
+
+    public void addSubstraitProjection(byte[] extendedExpression) throws IOException {
+        assert ioConfigBuilder != null;
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_add_substrait_projection(
+                ioConfigBuilder, extendedExpression, extendedExpression.length));
+    }
+
+    // only read rows of primary keys in columns of keys named by primary keys
+    public void setLookupKeys(VectorSchemaRoot keys) throws IOException {
+        assert ioConfigBuilder != null;
+        try (ArrowArray array = ArrowArray.allocateNew(allocator);
+             ArrowSchema schema = ArrowSchema.allocateNew(allocator)) {
+            Data.exportVectorSchemaRoot(allocator, keys, provider, array, schema);
+            takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_set_lookup_keys(
+                    ioConfigBuilder, schema.memoryAddress(), array.memoryAddress()));
+        }
+    }
+
+    public void addMergeOp(String column, String mergeOp) {
+        assert ioConfigBuilder != null;
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer mergeOpPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, mergeOp);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_merge_op(ioConfigBuilder, columnPtr, mergeOpPtr);
+    }
+
+    public void setCdcColumn(String cdcColumn, boolean changelog) {
+        assert ioConfigBuilder != null;
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, cdcColumn);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_cdc_column(ioConfigBuilder, columnPtr);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_cdc_changelog(ioConfigBuilder, changelog);
+    }
+
+    public void setDefaultColumnValue(String column, String value) {
+        assert ioConfigBuilder != null;
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer valuePtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, value);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_default_column_value(ioConfigBuilder, columnPtr, valuePtr);
+    }
+
+    public void setLimit(int limit) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_limit(ioConfigBuilder, limit);
+    }
+
+    public void setKeepFileOrder(boolean keepFileOrder) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_keep_file_order(ioConfigBuilder, keepFileOrder);
+    }
+
+    // bucket id and primary key order are exposed as metadata of reader schema,
+    // "lakesoul.hash_bucket_id" and "lakesoul.sort_columns"
+    public void setHashBucketId(int hashBucketId) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_hash_bucket_id(ioConfigBuilder, hashBucketId);
+    }
+
+    public void setBloomFilterPruning(boolean enabled) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_bloom_filter_pruning(ioConfigBuilder, enabled);
+    }
+
+    public void setPageIndexPruning(boolean enabled) {
+        assert ioConfigBuilder != null;
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_page_index_pruning(ioConfigBuilder, enabled);
+    }
+
+    public void addPartitionValue(String column, String value) {
+        assert ioConfigBuilder != null;
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer valuePtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, value);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_partition_value(ioConfigBuilder, columnPtr, valuePtr);
+    }
+
+    public void setPartitionDesc(String partitionDesc) {
+        assert ioConfigBuilder != null;
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, partitionDesc);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_partition_desc(ioConfigBuilder, ptr);
+    }
+
+    public void setFileByteRange(String file, long start, long length) {
+        assert ioConfigBuilder != null;
+        Pointer filePtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, file);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_file_byte_range(ioConfigBuilder, filePtr, start, length);
+    }
+
+    public void setFileRowGroups(String file, long[] rowGroups) {
+        assert ioConfigBuilder != null;
+        Pointer filePtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, file);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_file_row_groups(ioConfigBuilder, filePtr, rowGroups, rowGroups.length);
+    }
+
+    public void setFileExistCols(String file, String existCols) {
+        assert ioConfigBuilder != null;
+        Pointer filePtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, file);
+        Pointer existColsPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, existCols);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_file_exist_cols(ioConfigBuilder, filePtr, existColsPtr);
     }
 
     public void initializeReader() throws IOException {
@@ -99,6 +217,16 @@ public class NativeIOReader extends NativeIOBase implements AutoCloseable {
         return readerSchema;
     }
 
+    // number of row groups skipped by filters so far, always 0 for the java reader
+    public long getPrunedRowGroups() {
+        return useJavaReader || reader == null ? 0 : libLakeSoulIO.lakesoul_reader_get_pruned_row_groups(reader);
+    }
+
+    // number of pages skipped by filters so far, always 0 for the java reader
+    public long getPrunedPages() {
+        return useJavaReader || reader == null ? 0 : libLakeSoulIO.lakesoul_reader_get_pruned_pages(reader);
+    }
+
     private Schema getReaderSchema() {
         if (!useJavaReader) {
             ArrowSchema ffiSchema = ArrowSchema.allocateNew(allocator);
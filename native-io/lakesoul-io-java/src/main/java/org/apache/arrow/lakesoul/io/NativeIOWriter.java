@@ -36,13 +36,6 @@ public class NativeIOWriter extends NativeIOBase implements AutoCloseable {
         setSchema(schema);
     }
 
-    public void setPrimaryKeys(Iterable<String> primaryKeys) {
-        for (String pk : primaryKeys) {
-            Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, pk);
-            ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_single_primary_key(ioConfigBuilder, ptr);
-        }
-    }
-
     public void setAuxSortColumns(Iterable<String> auxSortColumns) {
         for (String col : auxSortColumns) {
             Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, col);
@@ -50,10 +43,90 @@ public class NativeIOWriter extends NativeIOBase implements AutoCloseable {
         }
     }
 
+    public void setRangePartitions(Iterable<String> rangePartitions) {
+        for (String col : rangePartitions) {
+            Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, col);
+            ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_add_single_range_partition(ioConfigBuilder, ptr);
+        }
+    }
+
+    public void setTablePath(String tablePath) {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, tablePath);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_table_path(ioConfigBuilder, ptr);
+    }
+
+    public void setFileNamePrefix(String prefix) {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, prefix);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_file_name_prefix(ioConfigBuilder, ptr);
+    }
+
     public void setRowGroupRowNumber(int rowNum) {
         ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_max_row_group_size(ioConfigBuilder, rowNum);
     }
 
+    public void setMaxFileSize(long maxFileSize) {
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_max_file_size(ioConfigBuilder, maxFileSize);
+    }
+
+    public void setMaxFileRows(long maxFileRows) {
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_max_file_rows(ioConfigBuilder, maxFileRows);
+    }
+
+    public void setCompression(String codec) throws IOException {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, codec);
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_set_compression(ioConfigBuilder, ptr));
+    }
+
+    public void setColumnCompression(String column, String codec) throws IOException {
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer codecPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, codec);
+        takeConfigBuilderResult(libLakeSoulIO.lakesoul_config_builder_set_column_compression(ioConfigBuilder, columnPtr, codecPtr));
+    }
+
+    public void setDictionaryEnabled(boolean enabled) {
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_dictionary_enabled(ioConfigBuilder, enabled);
+    }
+
+    public void setColumnDictionaryEnabled(String column, boolean enabled) {
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_column_dictionary_enabled(ioConfigBuilder, columnPtr, enabled);
+    }
+
+    public void setEncoding(String encoding) {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, encoding);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_encoding(ioConfigBuilder, ptr);
+    }
+
+    public void setColumnEncoding(String column, String encoding) {
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer encodingPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, encoding);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_column_encoding(ioConfigBuilder, columnPtr, encodingPtr);
+    }
+
+    public void setStatisticsEnabled(String level) {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, level);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_statistics_enabled(ioConfigBuilder, ptr);
+    }
+
+    public void setColumnStatisticsEnabled(String column, String level) {
+        Pointer columnPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, column);
+        Pointer levelPtr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, level);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_column_statistics_enabled(ioConfigBuilder, columnPtr, levelPtr);
+    }
+
+    public void setDataPageSize(long dataPageSize) {
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_data_page_size(ioConfigBuilder, dataPageSize);
+    }
+
+    public void setDictionaryPageSize(long dictionaryPageSize) {
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_dictionary_page_size(ioConfigBuilder, dictionaryPageSize);
+    }
+
+    public void setCreatedBy(String createdBy) {
+        Pointer ptr = LibLakeSoulIO.buildStringPointer(libLakeSoulIO, createdBy);
+        ioConfigBuilder = libLakeSoulIO.lakesoul_config_builder_set_created_by(ioConfigBuilder, ptr);
+    }
+
     public void initializeWriter() throws IOException {
         assert tokioRuntimeBuilder != null;
         assert ioConfigBuilder != null;
@@ -104,6 +177,31 @@ public class NativeIOWriter extends NativeIOBase implements AutoCloseable {
         }
     }
 
+    /**
+     * Flush and close the writer like {@link #flush()}.
+     *
+     * @return json array of written files with their path, partition desc, size, number of rows and
+     * row groups, columns and column statistics
+     */
+    public String flushWithFiles() throws IOException {
+        AtomicReference<String> errMsg = new AtomicReference<>();
+        AtomicReference<String> files = new AtomicReference<>();
+        Callback nativeCallback = new Callback((status, result) -> {
+            if (status) {
+                files.set(result);
+            } else if (result != null) {
+                errMsg.set(result);
+            }
+        }, referenceManager);
+        nativeCallback.registerReferenceKey();
+        libLakeSoulIO.flush_and_close_writer_with_files(writer, nativeCallback);
+        writer = null;
+        if (errMsg.get() != null && !errMsg.get().isEmpty()) {
+            throw new IOException("Native writer flush failed with error: " + errMsg.get());
+        }
+        return files.get();
+    }
+
     @Override
     public void close() throws Exception {
         if (writer != null) {
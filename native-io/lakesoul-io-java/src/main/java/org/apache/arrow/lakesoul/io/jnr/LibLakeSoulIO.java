@@ -46,20 +46,99 @@ public interface LibLakeSoulIO {
 
     Pointer lakesoul_config_builder_add_single_aux_sort_column(Pointer builder, Pointer column);
 
+    @Deprecated
     Pointer lakesoul_config_builder_add_filter(Pointer builder, Pointer filter);
 
+    Pointer lakesoul_config_builder_add_filter_checked(Pointer builder, Pointer filter);
+
+    Pointer lakesoul_config_builder_add_sql_filter(Pointer builder, Pointer sql);
+
+    Pointer lakesoul_config_builder_add_sql_projection(Pointer builder, Pointer sql, Pointer alias);
+
+    Pointer lakesoul_config_builder_add_substrait_filter(Pointer builder, byte[] extendedExpression, int len);
+
+    Pointer lakesoul_config_builder_add_substrait_projection(Pointer builder, byte[] extendedExpression, int len);
+
+    Pointer check_config_builder_result(Pointer result);
+
+    Pointer take_config_builder_from_result(Pointer result);
+
+    Pointer lakesoul_config_builder_add_merge_op(Pointer builder, Pointer column, Pointer mergeOp);
+
+    Pointer lakesoul_config_builder_set_cdc_column(Pointer builder, Pointer column);
+
+    Pointer lakesoul_config_builder_set_cdc_changelog(Pointer builder, boolean changelog);
+
+    Pointer lakesoul_config_builder_set_keep_file_order(Pointer builder, boolean keepFileOrder);
+
+    Pointer lakesoul_config_builder_set_hash_bucket_num(Pointer builder, long hashBucketNum);
+
+    Pointer lakesoul_config_builder_set_hash_bucket_id(Pointer builder, long hashBucketId);
+
+    Pointer lakesoul_config_builder_set_bloom_filter_pruning(Pointer builder, boolean enabled);
+
+    Pointer lakesoul_config_builder_set_page_index_pruning(Pointer builder, boolean enabled);
+
+    Pointer lakesoul_config_builder_add_single_range_partition(Pointer builder, Pointer column);
+
+    Pointer lakesoul_config_builder_set_table_path(Pointer builder, Pointer tablePath);
+
+    Pointer lakesoul_config_builder_set_file_name_prefix(Pointer builder, Pointer prefix);
+
     Pointer lakesoul_config_builder_set_schema(Pointer builder, long schemaAddr);
 
+    Pointer lakesoul_config_builder_set_lookup_keys(Pointer builder, long schemaAddr, long arrayAddr);
+
+    Pointer lakesoul_config_builder_set_default_column_value(Pointer builder, Pointer column, Pointer value);
+
+    Pointer lakesoul_config_builder_add_partition_value(Pointer builder, Pointer column, Pointer value);
+
+    Pointer lakesoul_config_builder_set_partition_desc(Pointer builder, Pointer partitionDesc);
+
+    Pointer lakesoul_config_builder_set_file_byte_range(Pointer builder, Pointer file, long start, long length);
+
+    Pointer lakesoul_config_builder_set_file_row_groups(Pointer builder, Pointer file, long[] rowGroups, int rowGroupNum);
+
+    Pointer lakesoul_config_builder_set_file_exist_cols(Pointer builder, Pointer file, Pointer existCols);
+
     Pointer lakesoul_config_builder_set_object_store_option(Pointer builder, Pointer key, Pointer value);
 
     Pointer lakesoul_config_builder_set_thread_num(Pointer builder, int thread_num);
 
     Pointer lakesoul_config_builder_set_batch_size(Pointer builder, int batch_size);
 
+    Pointer lakesoul_config_builder_set_limit(Pointer builder, int limit);
+
     Pointer lakesoul_config_builder_set_buffer_size(Pointer builder, int buffer_size);
 
     Pointer lakesoul_config_builder_set_max_row_group_size(Pointer builder, int row_group_size);
 
+    Pointer lakesoul_config_builder_set_max_file_size(Pointer builder, long max_file_size);
+
+    Pointer lakesoul_config_builder_set_max_file_rows(Pointer builder, long max_file_rows);
+
+    Pointer lakesoul_config_builder_set_compression(Pointer builder, Pointer codec);
+
+    Pointer lakesoul_config_builder_set_column_compression(Pointer builder, Pointer column, Pointer codec);
+
+    Pointer lakesoul_config_builder_set_dictionary_enabled(Pointer builder, boolean enabled);
+
+    Pointer lakesoul_config_builder_set_column_dictionary_enabled(Pointer builder, Pointer column, boolean enabled);
+
+    Pointer lakesoul_config_builder_set_encoding(Pointer builder, Pointer encoding);
+
+    Pointer lakesoul_config_builder_set_column_encoding(Pointer builder, Pointer column, Pointer encoding);
+
+    Pointer lakesoul_config_builder_set_statistics_enabled(Pointer builder, Pointer level);
+
+    Pointer lakesoul_config_builder_set_column_statistics_enabled(Pointer builder, Pointer column, Pointer level);
+
+    Pointer lakesoul_config_builder_set_data_page_size(Pointer builder, long data_page_size);
+
+    Pointer lakesoul_config_builder_set_dictionary_page_size(Pointer builder, long dictionary_page_size);
+
+    Pointer lakesoul_config_builder_set_created_by(Pointer builder, Pointer created_by);
+
     Pointer create_lakesoul_io_config_from_builder(Pointer builder);
 
     Pointer create_lakesoul_reader_from_config(Pointer config, Pointer runtime);
@@ -68,6 +147,10 @@ public interface LibLakeSoulIO {
 
     void lakesoul_reader_get_schema(Pointer reader, long schemaAddr);
 
+    long lakesoul_reader_get_pruned_row_groups(Pointer reader);
+
+    long lakesoul_reader_get_pruned_pages(Pointer reader);
+
     Pointer create_lakesoul_writer_from_config(Pointer config, Pointer runtime);
 
     Pointer check_writer_created(Pointer writer);
@@ -87,5 +170,7 @@ public interface LibLakeSoulIO {
 
     void flush_and_close_writer(Pointer writer, JavaCallback callback);
 
+    void flush_and_close_writer_with_files(Pointer writer, JavaCallback callback);
+
     void free_tokio_runtime(Pointer runtime);
 }
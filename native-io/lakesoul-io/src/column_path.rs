@@ -0,0 +1,356 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use arrow::array::{Array, MapArray, UInt32Builder};
+use arrow::compute::take;
+use arrow_schema::{DataType, Field, Schema};
+use datafusion::common::Column;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{
+    ColumnarValue, Expr, GetIndexedField, ReturnTypeFunction, ScalarFunctionImplementation, ScalarUDF, Signature,
+    Volatility,
+};
+use datafusion::scalar::ScalarValue;
+use std::fmt;
+use std::sync::Arc;
+
+/// One step of a nested column path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// field of a struct, `a.b`
+    Field(String),
+    /// element of a list, `a[1]`, indexed the same as DataFusion
+    Index(i64),
+    /// value of a map, `a['k']`
+    Key(String),
+}
+
+/// Path of a top-level column or a value nested in it, like `a.b[1]['k']`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnPath {
+    pub root: String,
+    pub segments: Vec<PathSegment>,
+}
+
+impl ColumnPath {
+    /// Resolve a path against schema, a top-level column whose name contains dots
+    /// takes precedence over a nested path. Returns the path, the expression
+    /// to get its value and the type of the value.
+    pub fn resolve(path: &str, schema: &Schema) -> Result<(ColumnPath, Expr, DataType)> {
+        let (root, rest) = split_root(path, schema)?;
+        let column_path = ColumnPath {
+            root: root.to_string(),
+            segments: parse_segments(rest)
+                .map_err(|e| DataFusionError::Plan(format!("invalid column path {}: {}", path, e)))?,
+        };
+        let (expr, data_type) = column_path.expr(schema)?;
+        Ok((column_path, expr, data_type))
+    }
+
+    /// The expression to get the value of the path in schema and the type of the value
+    pub fn expr(&self, schema: &Schema) -> Result<(Expr, DataType)> {
+        let root = self.root.as_str();
+        let mut expr = Expr::Column(Column::from_name(root));
+        let mut data_type = schema.field_with_name(root)?.data_type().clone();
+        for segment in &self.segments {
+            let (next_expr, next_type) = match (segment, &data_type) {
+                (PathSegment::Field(name), DataType::Struct(fields)) => {
+                    let field = fields.iter().find(|f| f.name() == name).ok_or_else(|| {
+                        DataFusionError::Plan(format!("no field {} in {} of column path {}", name, root, self))
+                    })?;
+                    (
+                        Expr::GetIndexedField(GetIndexedField::new(
+                            Box::new(expr),
+                            ScalarValue::Utf8(Some(name.clone())),
+                        )),
+                        field.data_type().clone(),
+                    )
+                }
+                (
+                    PathSegment::Index(idx),
+                    DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _),
+                ) => (
+                    Expr::GetIndexedField(GetIndexedField::new(Box::new(expr), ScalarValue::Int64(Some(*idx)))),
+                    field.data_type().clone(),
+                ),
+                (PathSegment::Key(key), DataType::Map(entries, _)) => match entries.data_type() {
+                    DataType::Struct(fields) if fields.len() == 2 => {
+                        let key = ScalarValue::try_from_string(key.clone(), fields[0].data_type())?;
+                        (
+                            map_value_udf().call(vec![expr, Expr::Literal(key)]),
+                            fields[1].data_type().clone(),
+                        )
+                    }
+                    data_type => {
+                        return Err(DataFusionError::Internal(format!(
+                            "unexpected map entries type {:?}",
+                            data_type
+                        )))
+                    }
+                },
+                (segment, data_type) => {
+                    return Err(DataFusionError::Plan(format!(
+                        "{:?} could not be applied to type {:?} in column path {}",
+                        segment, data_type, self
+                    )))
+                }
+            };
+            expr = next_expr;
+            data_type = next_type;
+        }
+        Ok((expr, data_type))
+    }
+
+    /// Names of struct fields from the root until the first list or map access
+    pub fn struct_prefix(&self) -> Vec<String> {
+        self.segments
+            .iter()
+            .map_while(|segment| match segment {
+                PathSegment::Field(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Split a path into the top-level column and the rest like `.b[1]`, trying the longest
+/// column name first so that a top-level column whose name contains dots takes precedence
+pub fn split_root<'a>(path: &'a str, schema: &Schema) -> Result<(&'a str, &'a str)> {
+    let mut boundaries = path
+        .char_indices()
+        .filter(|(_, ch)| *ch == '.' || *ch == '[')
+        .map(|(idx, _)| idx)
+        .collect::<Vec<usize>>();
+    boundaries.push(path.len());
+    let root_end = boundaries
+        .into_iter()
+        .rev()
+        .find(|end| schema.field_with_name(&path[..*end]).is_ok())
+        .ok_or_else(|| DataFusionError::Plan(format!("unknown column {}", path)))?;
+    Ok(path.split_at(root_end))
+}
+
+impl fmt::Display for ColumnPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root)?;
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Field(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(idx) => write!(f, "[{}]", idx)?,
+                // keys with single quotes are double quoted
+                PathSegment::Key(key) if key.contains('\'') => write!(f, "[\"{}\"]", key)?,
+                PathSegment::Key(key) => write!(f, "['{}']", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// segments after the root, like `.b[1]['k']`
+fn parse_segments(s: &str) -> std::result::Result<Vec<PathSegment>, String> {
+    let mut segments = vec![];
+    let mut rest = s;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(|ch| ch == '.' || ch == '[').unwrap_or(r.len());
+            if end == 0 {
+                return Err("empty field name".to_string());
+            }
+            segments.push(PathSegment::Field(r[..end].to_string()));
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            match ['\'', '"'].into_iter().find(|q| r.starts_with(*q)) {
+                // a quoted key ends with the quote followed by ']', so it could contain ']'
+                Some(quote) => {
+                    let end = r[1..]
+                        .find(&format!("{}]", quote))
+                        .ok_or_else(|| "unclosed '['".to_string())?
+                        + 1;
+                    segments.push(PathSegment::Key(r[1..end].to_string()));
+                    rest = &r[end + 2..];
+                }
+                None => {
+                    let end = r.find(']').ok_or_else(|| "unclosed '['".to_string())?;
+                    let inner = &r[..end];
+                    segments.push(PathSegment::Index(
+                        inner
+                            .trim()
+                            .parse::<i64>()
+                            .map_err(|e| format!("invalid index {}: {}", inner, e))?,
+                    ));
+                    rest = &r[end + 1..];
+                }
+            }
+        } else {
+            return Err(format!("unexpected {}", rest));
+        }
+    }
+    Ok(segments)
+}
+
+/// Prune a struct field to the fields selected by paths of struct field names,
+/// an empty path selects the whole field
+pub fn prune_field(field: &Field, paths: &[&[String]]) -> Field {
+    if paths.iter().any(|p| p.is_empty()) {
+        return field.clone();
+    }
+    match field.data_type() {
+        DataType::Struct(children) => {
+            let children = children
+                .iter()
+                .filter_map(|child| {
+                    let sub_paths = paths
+                        .iter()
+                        .filter(|p| &p[0] == child.name())
+                        .map(|p| &p[1..])
+                        .collect::<Vec<_>>();
+                    if sub_paths.is_empty() {
+                        None
+                    } else {
+                        Some(prune_field(child, &sub_paths))
+                    }
+                })
+                .collect();
+            Field::new(field.name(), DataType::Struct(children), field.is_nullable())
+        }
+        _ => field.clone(),
+    }
+}
+
+// Value of a key in each map, or null if the key doesn't exist
+fn map_value_udf() -> ScalarUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|args| match &args[0] {
+        DataType::Map(entries, _) => match entries.data_type() {
+            DataType::Struct(fields) if fields.len() == 2 => Ok(Arc::new(fields[1].data_type().clone())),
+            data_type => Err(DataFusionError::Internal(format!(
+                "unexpected map entries type {:?}",
+                data_type
+            ))),
+        },
+        data_type => Err(DataFusionError::Internal(format!(
+            "map_value does not support type {:?}",
+            data_type
+        ))),
+    });
+    let fun: ScalarFunctionImplementation = Arc::new(|args: &[ColumnarValue]| {
+        let array = args[0].clone().into_array(1);
+        let map = array
+            .as_any()
+            .downcast_ref::<MapArray>()
+            .ok_or_else(|| DataFusionError::Internal("map_value expects a map".to_string()))?;
+        let key = match &args[1] {
+            ColumnarValue::Scalar(key) => key,
+            ColumnarValue::Array(_) => {
+                return Err(DataFusionError::Internal("map_value expects a literal key".to_string()));
+            }
+        };
+        let keys = map.keys();
+        let offsets = map.value_offsets();
+        let mut indices = UInt32Builder::with_capacity(map.len());
+        for i in 0..map.len() {
+            if map.is_null(i) {
+                indices.append_null();
+                continue;
+            }
+            let mut found = None;
+            for j in offsets[i] as usize..offsets[i + 1] as usize {
+                if &ScalarValue::try_from_array(&keys, j)? == key {
+                    found = Some(j as u32);
+                }
+            }
+            indices.append_option(found);
+        }
+        Ok(ColumnarValue::Array(take(
+            map.values().as_ref(),
+            &indices.finish(),
+            None,
+        )?))
+    });
+    ScalarUDF::new(
+        "map_value",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_schema() -> Schema {
+        let inner = DataType::Struct(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Utf8, true),
+        ]);
+        Schema::new(vec![
+            Field::new("a.b", DataType::Int32, true),
+            Field::new("s", inner.clone(), true),
+            Field::new("l", DataType::List(Box::new(Field::new("item", inner, true))), true),
+        ])
+    }
+
+    #[test]
+    fn test_resolve_column_path() -> Result<()> {
+        let schema = test_schema();
+
+        let (path, expr, data_type) = ColumnPath::resolve("a.b", &schema)?;
+        assert!(path.segments.is_empty());
+        assert_eq!(expr, Expr::Column(Column::from_name("a.b")));
+        assert_eq!(data_type, DataType::Int32);
+
+        let (path, _, data_type) = ColumnPath::resolve("s.y", &schema)?;
+        assert_eq!(path.root, "s");
+        assert_eq!(path.struct_prefix(), vec!["y".to_string()]);
+        assert_eq!(data_type, DataType::Utf8);
+
+        let (path, _, data_type) = ColumnPath::resolve("l[1].x", &schema)?;
+        assert_eq!(
+            path.segments,
+            vec![PathSegment::Index(1), PathSegment::Field("x".to_string())]
+        );
+        assert!(path.struct_prefix().is_empty());
+        assert_eq!(data_type, DataType::Int32);
+
+        // keys containing quotes or ']' are displayed as paths parsed back to the same keys
+        for key in ["k", "it's", "a]b"] {
+            let path = ColumnPath {
+                root: "m".to_string(),
+                segments: vec![PathSegment::Key(key.to_string())],
+            };
+            assert_eq!(
+                parse_segments(&path.to_string()[1..]),
+                Ok(vec![PathSegment::Key(key.to_string())])
+            );
+        }
+
+        assert!(ColumnPath::resolve("s.z", &schema).is_err());
+        assert!(ColumnPath::resolve("s[1]", &schema).is_err());
+        assert!(ColumnPath::resolve("t", &schema).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_field() {
+        let schema = test_schema();
+        let y = vec!["y".to_string()];
+        let pruned = prune_field(schema.field_with_name("s").unwrap(), &[y.as_slice()]);
+        assert_eq!(
+            pruned.data_type(),
+            &DataType::Struct(vec![Field::new("y", DataType::Utf8, true)])
+        );
+    }
+}
@@ -14,4 +14,8 @@
  * limitations under the License.
  */
 mod parser;
-pub use parser::Parser;
\ No newline at end of file
+mod sql;
+mod substrait;
+pub use self::substrait::{decode_extended_expression, DecodedExpr};
+pub use parser::{ParseError, ParseErrorKind, ParseResult, Parser};
+pub use sql::{parse_sql_expr, parse_sql_filter};
@@ -14,195 +14,826 @@
  * limitations under the License.
  */
 
-use arrow_schema::{DataType, Field, SchemaRef};
-use datafusion::logical_expr::{col, Expr};
+use crate::column_path::ColumnPath;
+use arrow::array::{as_primitive_array, BooleanArray};
+use arrow::datatypes::{Float32Type, Float64Type};
+use arrow_schema::{DataType, Field, SchemaRef, TimeUnit};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Cast, ColumnarValue, Expr, Operator, ReturnTypeFunction, ScalarFunctionImplementation,
+    ScalarUDF, Signature, Volatility,
+};
 use datafusion::scalar::ScalarValue;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
+/// What went wrong when parsing a filter string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedEnd,
+    Expected {
+        expected: String,
+        found: char,
+    },
+    UnknownOperator(String),
+    UnknownColumn(String),
+    InvalidLiteral {
+        value: String,
+        data_type: DataType,
+        reason: String,
+    },
+    UnsupportedType {
+        column: String,
+        data_type: DataType,
+    },
+    TrailingCharacters,
+}
+
+/// Error of parsing a filter string, `position` is the byte offset where it occurs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of filter"),
+            ParseErrorKind::Expected { expected, found } => write!(f, "expected {} but found '{}'", expected, found),
+            ParseErrorKind::UnknownOperator(op) => write!(f, "unknown operator '{}'", op),
+            ParseErrorKind::UnknownColumn(column) => write!(f, "unknown column '{}'", column),
+            ParseErrorKind::InvalidLiteral {
+                value,
+                data_type,
+                reason,
+            } => write!(f, "invalid {:?} literal '{}': {}", data_type, value, reason),
+            ParseErrorKind::UnsupportedType { column, data_type } => {
+                write!(
+                    f,
+                    "column '{}' of type {:?} is not supported in filters",
+                    column, data_type
+                )
+            }
+            ParseErrorKind::TrailingCharacters => write!(f, "unexpected characters after filter"),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {} of filter", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type ParseResult<T> = std::result::Result<T, ParseError>;
+
+/// Parser of filters encoded by `FilterPredicate.toString()` of parquet-mr,
+/// e.g. `and(eq(a, 1), noteq(b, Binary{"x"}))`
 pub struct Parser {}
 
 impl Parser {
-    pub fn parse(filter_str: String, schema: SchemaRef) -> Expr {
-        let (op, left, right) = Parser::parse_filter_str(filter_str);
-        if right == "null" {
-            match op.as_str() {
-                "eq" => {
-                    let column = col(left.as_str());
-                    column.is_null()
-                }
-                "noteq" => {
-                    let column = col(left.as_str());
-                    column.is_not_null()
-                }
-                _ => Expr::Wildcard,
+    pub fn parse(filter_str: String, schema: SchemaRef) -> ParseResult<Expr> {
+        let mut parser = FilterParser {
+            input: filter_str.as_str(),
+            pos: 0,
+            schema,
+        };
+        let expr = parser.parse_expr()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.input.len() {
+            return Err(parser.error(ParseErrorKind::TrailingCharacters));
+        }
+        Ok(expr)
+    }
+}
+
+struct FilterParser<'a> {
+    input: &'a str,
+    pos: usize,
+    schema: SchemaRef,
+}
+
+impl<'a> FilterParser<'a> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.pos,
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
             }
-        } else {
-            match op.as_str() {
-                "not" => {
-                    let inner = Parser::parse(right, schema);
-                    Expr::not(inner)
-                }
-                "eq" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.eq(value)
-                }
-                "noteq" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.not_eq(value)
-                }
-                "or" => {
-                    let left_expr = Parser::parse(left, schema.clone());
-                    let right_expr = Parser::parse(right, schema);
-                    left_expr.or(right_expr)
-                }
-                "and" => {
-                    let left_expr = Parser::parse(left, schema.clone());
-                    let right_expr = Parser::parse(right, schema);
-                    left_expr.and(right_expr)
+            self.pos += ch.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(ch) if ch == expected => {
+                self.pos += ch.len_utf8();
+                Ok(())
+            }
+            Some(ch) => Err(self.error(ParseErrorKind::Expected {
+                expected: format!("'{}'", expected),
+                found: ch,
+            })),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    // op(args)
+    fn parse_expr(&mut self) -> ParseResult<Expr> {
+        self.skip_whitespace();
+        let op_pos = self.pos;
+        while let Some(ch) = self.peek() {
+            if !ch.is_ascii_alphanumeric() {
+                break;
+            }
+            self.pos += 1;
+        }
+        let input = self.input;
+        let op = &input[op_pos..self.pos];
+        if op.is_empty() {
+            return match self.peek() {
+                Some(ch) => Err(self.error(ParseErrorKind::Expected {
+                    expected: "operator".to_string(),
+                    found: ch,
+                })),
+                None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+            };
+        }
+        self.expect('(')?;
+        let expr = match op {
+            "and" | "or" => {
+                let left = self.parse_expr()?;
+                self.expect(',')?;
+                let right = self.parse_expr()?;
+                if op == "and" {
+                    left.and(right)
+                } else {
+                    left.or(right)
                 }
-                "gt" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.gt(value)
+            }
+            "not" => Expr::not(self.parse_expr()?),
+            "eq" | "noteq" | "gt" | "gteq" | "lt" | "lteq" => {
+                let (column, field, _) = self.parse_column()?;
+                match self.parse_argument(&field)? {
+                    (None, _) if op == "eq" => column.is_null(),
+                    (None, _) if op == "noteq" => column.is_not_null(),
+                    (None, value_pos) => return Err(null_not_comparable(&field, op, value_pos)),
+                    (Some(value), _) => {
+                        let value = literal(&field, value);
+                        match op {
+                            "eq" => column.eq(value),
+                            "noteq" => column.not_eq(value),
+                            "gt" => column.gt(value),
+                            "gteq" => column.gt_eq(value),
+                            "lt" => column.lt(value),
+                            _ => column.lt_eq(value),
+                        }
+                    }
                 }
-                "gteq" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.gt_eq(value)
+            }
+            // eqNullSafe(a, null) is true for null values of a
+            "eqNullSafe" => {
+                let (column, field, _) = self.parse_column()?;
+                match self.parse_argument(&field)? {
+                    (None, _) => column.is_null(),
+                    (Some(value), _) => Expr::BinaryExpr(BinaryExpr {
+                        left: Box::new(column),
+                        op: Operator::IsNotDistinctFrom,
+                        right: Box::new(literal(&field, value)),
+                    }),
                 }
-                "lt" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.lt(value)
+            }
+            // in(a, [1, 2, 3]) and notIn(a, [1, 2, 3])
+            "in" | "notIn" => {
+                let (column, field, _) = self.parse_column()?;
+                self.expect(',')?;
+                self.expect('[')?;
+                let mut values = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(']') && values.is_empty() {
+                        break;
+                    }
+                    let value_pos = self.pos;
+                    let value = self.parse_value()?;
+                    values.push(literal(&field, parse_nullable_literal(&field, value, value_pos)?));
+                    self.skip_whitespace();
+                    if self.peek() != Some(',') {
+                        break;
+                    }
+                    self.pos += 1;
                 }
-                "lteq" => {
-                    let column = col(left.as_str());
-                    let value = Parser::parse_literal(left, right, schema);
-                    column.lt_eq(value)
+                self.expect(']')?;
+                column.in_list(values, op == "notIn")
+            }
+            // between(a, low, high), both bounds are inclusive
+            "between" => {
+                let (column, field, _) = self.parse_column()?;
+                let (low, low_pos) = self.parse_argument(&field)?;
+                let low = low.ok_or_else(|| null_not_comparable(&field, op, low_pos))?;
+                let (high, high_pos) = self.parse_argument(&field)?;
+                let high = high.ok_or_else(|| null_not_comparable(&field, op, high_pos))?;
+                Expr::Between(Between {
+                    expr: Box::new(column),
+                    negated: false,
+                    low: Box::new(literal(&field, low)),
+                    high: Box::new(literal(&field, high)),
+                })
+            }
+            // string matching, patterns of like are SQL patterns with '%' and '_'
+            "startsWith" | "contains" | "like" => {
+                let (column, field, column_pos) = self.parse_column()?;
+                let (value, value_pos) = self.parse_argument(&field)?;
+                let (pattern, large) = match value {
+                    Some(ScalarValue::Utf8(Some(s))) => (s, false),
+                    Some(ScalarValue::LargeUtf8(Some(s))) => (s, true),
+                    Some(_) => {
+                        return Err(ParseError {
+                            position: column_pos,
+                            kind: ParseErrorKind::UnsupportedType {
+                                column: field.name().clone(),
+                                data_type: field.data_type().clone(),
+                            },
+                        })
+                    }
+                    None => return Err(null_not_comparable(&field, op, value_pos)),
+                };
+                let pattern = match op {
+                    "startsWith" => format!("{}%", escape_like_pattern(&pattern)),
+                    "contains" => format!("%{}%", escape_like_pattern(&pattern)),
+                    _ => pattern,
+                };
+                let pattern = if large {
+                    ScalarValue::LargeUtf8(Some(pattern))
+                } else {
+                    ScalarValue::Utf8(Some(pattern))
+                };
+                column.like(Expr::Literal(pattern))
+            }
+            "isNaN" => {
+                let (column, field, column_pos) = self.parse_column()?;
+                if !matches!(field.data_type(), DataType::Float32 | DataType::Float64) {
+                    return Err(ParseError {
+                        position: column_pos,
+                        kind: ParseErrorKind::UnsupportedType {
+                            column: field.name().clone(),
+                            data_type: field.data_type().clone(),
+                        },
+                    });
                 }
+                is_nan_udf().call(vec![column])
+            }
+            _ => {
+                return Err(ParseError {
+                    position: op_pos,
+                    kind: ParseErrorKind::UnknownOperator(op.to_string()),
+                })
+            }
+        };
+        self.expect(')')?;
+        Ok(expr)
+    }
 
-                _ => Expr::Wildcard,
+    // column name, which may contain dots, ends before ',' or ')'.
+    // Returns the column, its field in schema and its position.
+    fn parse_column(&mut self) -> ParseResult<(Expr, Field, usize)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        // map keys in brackets may contain ',' or ')'
+        let mut in_brackets = false;
+        while let Some(ch) = self.peek() {
+            match ch {
+                ',' | ')' if !in_brackets => break,
+                '[' => in_brackets = true,
+                ']' => in_brackets = false,
+                _ => {}
             }
+            self.pos += ch.len_utf8();
+        }
+        let name = self.input[start..self.pos].trim_end();
+        if name.is_empty() {
+            return Err(match self.peek() {
+                Some(ch) => self.error(ParseErrorKind::Expected {
+                    expected: "column".to_string(),
+                    found: ch,
+                }),
+                None => self.error(ParseErrorKind::UnexpectedEnd),
+            });
+        }
+        // a nested column path like `a.b[1]` is typed as the value it refers to
+        match ColumnPath::resolve(name, &self.schema) {
+            Ok((_, expr, data_type)) => Ok((expr, Field::new(name, data_type, true), start)),
+            Err(_) => Err(ParseError {
+                position: start,
+                kind: ParseErrorKind::UnknownColumn(name.to_string()),
+            }),
         }
     }
 
-    fn parse_filter_str(filter: String) -> (String, String, String) {
-        let op_offset = filter.find('(').unwrap();
-        let (op, filter) = filter.split_at(op_offset);
-        if !filter.ends_with(")") {
-            panic!("Invalid filter string");
+    // ", value" of a column, None for null. Returns the value and its position.
+    fn parse_argument(&mut self, field: &Field) -> ParseResult<(Option<ScalarValue>, usize)> {
+        self.expect(',')?;
+        self.skip_whitespace();
+        let value_pos = self.pos;
+        let value = self.parse_value()?;
+        if value == "null" {
+            Ok((None, value_pos))
+        } else {
+            Ok((Some(parse_literal(field, value, value_pos)?), value_pos))
         }
-        let filter = &filter[1..filter.len() - 1];
-        let mut k: i8 = 0;
-        let mut left_offset: usize = 0;
-        for (i, ch) in filter.chars().enumerate() {
-            match ch {
-                '(' => k += 1,
-                ')' => k -= 1,
-                ',' => {
-                    if k == 0 && left_offset == 0 {
-                        left_offset = i
-                    }
+    }
+
+    // Raw text of a literal value, ends before ',', ')' or ']' outside of brackets.
+    // Strings are encoded as Binary{"..."} and could contain any character.
+    fn parse_value(&mut self) -> ParseResult<&'a str> {
+        let start = self.pos;
+        let rest = &self.input[start..];
+        if rest.starts_with("Binary{\"") {
+            return match rest.find("\"}") {
+                Some(end) => {
+                    self.pos += end + 2;
+                    Ok(&self.input[start..self.pos])
+                }
+                None => {
+                    self.pos = self.input.len();
+                    Err(self.error(ParseErrorKind::UnexpectedEnd))
                 }
+            };
+        }
+        let mut depth = 0;
+        while let Some(ch) = self.peek() {
+            match ch {
+                '[' | '{' | '(' => depth += 1,
+                ')' | ']' | ',' if depth == 0 => break,
+                ')' | ']' | '}' => depth -= 1,
                 _ => {}
             }
+            self.pos += ch.len_utf8();
         }
-        if k != 0 {
-            panic!("Invalid filter string");
+        if self.peek().is_none() {
+            return Err(self.error(ParseErrorKind::UnexpectedEnd));
         }
-        let (left, right) = filter.split_at(left_offset);
-        if op.eq("not") {
-            (op.to_string(), left.to_string(), right[0..].to_string())
-        } else {
-            (op.to_string(), left.to_string(), right[2..].to_string())
-        }
-    }
-
-    fn parse_literal(column: String, value: String, schema: SchemaRef) -> Expr {
-        let fields = schema
-            .fields()
-            .iter()
-            .filter(|field| field.name().eq(&column))
-            .collect::<Vec<&Field>>();
-        let data_type = fields.get(0).unwrap().data_type().clone();
-        match data_type {
-            DataType::Decimal128(precision, scale) => {
-                if precision <= 18 {
-                    Expr::Literal(ScalarValue::Decimal128(
-                        Some(value.parse::<i128>().unwrap()),
-                        precision,
-                        scale,
-                    ))
-                } else {
-                    let binary_vec = Parser::parse_binary_array(value.as_str()).unwrap();
-                    let mut arr = [0u8; 16];
-                    for idx in 0..binary_vec.len() {
-                        arr[idx + 16 - binary_vec.len()] = binary_vec[idx];
-                    }
-                    Expr::Literal(ScalarValue::Decimal128(
-                        Some(i128::from_be_bytes(arr)),
-                        precision,
-                        scale,
-                    ))
-                }
+        Ok(self.input[start..self.pos].trim_end())
+    }
+}
+
+fn null_not_comparable(field: &Field, op: &str, position: usize) -> ParseError {
+    ParseError {
+        position,
+        kind: ParseErrorKind::InvalidLiteral {
+            value: "null".to_string(),
+            data_type: field.data_type().clone(),
+            reason: format!("null could not be compared by {}", op),
+        },
+    }
+}
+
+// escape wildcards of like patterns so that they match literally
+pub(crate) fn escape_like_pattern(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+// isNaN is not a built-in function of DataFusion
+pub(crate) fn is_nan_udf() -> ScalarUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Boolean)));
+    let fun: ScalarFunctionImplementation = Arc::new(|args: &[ColumnarValue]| {
+        let array = match &args[0] {
+            ColumnarValue::Array(array) => array.clone(),
+            ColumnarValue::Scalar(scalar) => scalar.to_array(),
+        };
+        let result: BooleanArray = match array.data_type() {
+            DataType::Float32 => as_primitive_array::<Float32Type>(&array)
+                .iter()
+                .map(|v| v.map(|v| v.is_nan()))
+                .collect(),
+            DataType::Float64 => as_primitive_array::<Float64Type>(&array)
+                .iter()
+                .map(|v| v.map(|v| v.is_nan()))
+                .collect(),
+            data_type => {
+                return Err(DataFusionError::Internal(format!(
+                    "isNaN does not support type {:?}",
+                    data_type
+                )))
             }
-            DataType::Boolean => Expr::Literal(ScalarValue::Boolean(Some(value.parse::<bool>().unwrap()))),
-            DataType::Binary => Expr::Literal(ScalarValue::Binary(Parser::parse_binary_array(value.as_str()))),
-            DataType::Float32 => Expr::Literal(ScalarValue::Float32(Some(value.parse::<f32>().unwrap()))),
-            DataType::Float64 => Expr::Literal(ScalarValue::Float64(Some(value.parse::<f64>().unwrap()))),
-            DataType::Int8 => Expr::Literal(ScalarValue::Int8(Some(value.parse::<i8>().unwrap()))),
-            DataType::Int16 => Expr::Literal(ScalarValue::Int16(Some(value.parse::<i16>().unwrap()))),
-            DataType::Int32 => Expr::Literal(ScalarValue::Int32(Some(value.parse::<i32>().unwrap()))),
-            DataType::Int64 => Expr::Literal(ScalarValue::Int64(Some(value.parse::<i64>().unwrap()))),
-            DataType::Date32 => Expr::Literal(ScalarValue::Date32(Some(value.parse::<i32>().unwrap()))),
-            DataType::Timestamp(_, _) => Expr::Literal(ScalarValue::TimestampMicrosecond(
-                Some(value.parse::<i64>().unwrap()),
-                None,
-            )),
-            DataType::Utf8 => {
-                let value = value.as_str()[8..value.len() - 2].to_string();
-                Expr::Literal(ScalarValue::Utf8(Some(value)))
-            }
-            _ => Expr::Literal(ScalarValue::Utf8(Some(value))),
-        }
-    }
-
-    fn parse_binary_array(value: &str) -> Option<Vec<u8>> {
-        let left_bracket_pos = value.find('[').unwrap_or(0);
-        let right_bracket_pos = value.find(']').unwrap_or(0);
-        if left_bracket_pos == 0 {
-            None
-        } else if left_bracket_pos + 1 == right_bracket_pos {
-            Some(Vec::<u8>::new())
-        } else {
-            Some(
-                value[left_bracket_pos + 1..right_bracket_pos]
-                    .to_string()
-                    .replace(" ", "")
-                    .split(",")
-                    .collect::<Vec<&str>>()
-                    .iter()
-                    .map(|s| s.parse::<i16>().unwrap())
-                    .map(|s: i16| if s < 0 { (s + 256) as u8 } else { s as u8 })
-                    .collect::<Vec<u8>>(),
-            )
+        };
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    });
+    ScalarUDF::new(
+        "isNaN",
+        &Signature::uniform(1, vec![DataType::Float32, DataType::Float64], Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+// literal in a value list, which could be null
+fn parse_nullable_literal(field: &Field, value: &str, position: usize) -> ParseResult<ScalarValue> {
+    if value == "null" {
+        let data_type = match field.data_type() {
+            DataType::Decimal256(precision, scale) => DataType::Decimal128((*precision).min(38), *scale),
+            data_type => data_type.clone(),
+        };
+        ScalarValue::try_from(&data_type).map_err(|_| ParseError {
+            position,
+            kind: ParseErrorKind::UnsupportedType {
+                column: field.name().clone(),
+                data_type: field.data_type().clone(),
+            },
+        })
+    } else {
+        parse_literal(field, value, position)
+    }
+}
+
+// Literal expression of a value parsed by `parse_literal`. There is no 256-bit decimal scalar,
+// so such values are parsed as 128-bit decimals and cast to the column type.
+fn literal(field: &Field, value: ScalarValue) -> Expr {
+    match field.data_type() {
+        DataType::Decimal256(_, _) => Expr::Cast(Cast {
+            expr: Box::new(Expr::Literal(value)),
+            data_type: field.data_type().clone(),
+        }),
+        _ => Expr::Literal(value),
+    }
+}
+
+// Parse a literal value by column type. Values are encoded in the physical type of parquet:
+// timestamps, dates and times are integers in the unit of the column, strings and
+// binaries are Binary{"..."} or Binary{N constant bytes, [...]}, decimals are unscaled
+// integers or big-endian two's complement bytes.
+fn parse_literal(field: &Field, value: &str, position: usize) -> ParseResult<ScalarValue> {
+    parse_typed_literal(field.data_type(), value).map_err(|reason| ParseError {
+        position,
+        kind: match reason {
+            Some(reason) => ParseErrorKind::InvalidLiteral {
+                value: value.to_string(),
+                data_type: field.data_type().clone(),
+                reason,
+            },
+            None => ParseErrorKind::UnsupportedType {
+                column: field.name().clone(),
+                data_type: field.data_type().clone(),
+            },
+        },
+    })
+}
+
+// error is None for unsupported types
+fn parse_typed_literal(data_type: &DataType, value: &str) -> std::result::Result<ScalarValue, Option<String>> {
+    macro_rules! parse {
+        ($ty:ty) => {
+            Some(value.parse::<$ty>().map_err(|e| Some(e.to_string()))?)
+        };
+    }
+    Ok(match data_type {
+        DataType::Boolean => ScalarValue::Boolean(parse!(bool)),
+        DataType::Int8 => ScalarValue::Int8(parse!(i8)),
+        DataType::Int16 => ScalarValue::Int16(parse!(i16)),
+        DataType::Int32 => ScalarValue::Int32(parse!(i32)),
+        DataType::Int64 => ScalarValue::Int64(parse!(i64)),
+        DataType::UInt8 => ScalarValue::UInt8(parse!(u8)),
+        DataType::UInt16 => ScalarValue::UInt16(parse!(u16)),
+        DataType::UInt32 => ScalarValue::UInt32(parse!(u32)),
+        DataType::UInt64 => ScalarValue::UInt64(parse!(u64)),
+        DataType::Float32 => ScalarValue::Float32(parse!(f32)),
+        DataType::Float64 => ScalarValue::Float64(parse!(f64)),
+        DataType::Decimal128(precision, scale) => {
+            ScalarValue::Decimal128(Some(parse_decimal(value)?), *precision, *scale)
+        }
+        DataType::Decimal256(precision, scale) => {
+            ScalarValue::Decimal128(Some(parse_decimal(value)?), (*precision).min(38), *scale)
+        }
+        DataType::Date32 => ScalarValue::Date32(parse!(i32)),
+        DataType::Date64 => ScalarValue::Date64(parse!(i64)),
+        DataType::Time32(TimeUnit::Second) => ScalarValue::Time32Second(parse!(i32)),
+        DataType::Time32(TimeUnit::Millisecond) => ScalarValue::Time32Millisecond(parse!(i32)),
+        DataType::Time64(TimeUnit::Microsecond) => ScalarValue::Time64Microsecond(parse!(i64)),
+        DataType::Time64(TimeUnit::Nanosecond) => ScalarValue::Time64Nanosecond(parse!(i64)),
+        DataType::Timestamp(unit, tz) => {
+            let value = parse!(i64);
+            match unit {
+                TimeUnit::Second => ScalarValue::TimestampSecond(value, tz.clone()),
+                TimeUnit::Millisecond => ScalarValue::TimestampMillisecond(value, tz.clone()),
+                TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(value, tz.clone()),
+                TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(value, tz.clone()),
+            }
+        }
+        DataType::Utf8 => ScalarValue::Utf8(Some(parse_string(value)?)),
+        DataType::LargeUtf8 => ScalarValue::LargeUtf8(Some(parse_string(value)?)),
+        DataType::Binary => ScalarValue::Binary(Some(parse_bytes(value)?)),
+        DataType::LargeBinary => ScalarValue::LargeBinary(Some(parse_bytes(value)?)),
+        DataType::FixedSizeBinary(size) => {
+            let bytes = parse_bytes(value)?;
+            if bytes.len() != *size as usize {
+                return Err(Some(format!("expected {} bytes but found {}", size, bytes.len())));
+            }
+            ScalarValue::FixedSizeBinary(*size, Some(bytes))
+        }
+        DataType::Dictionary(key_type, value_type) => {
+            ScalarValue::Dictionary(key_type.clone(), Box::new(parse_typed_literal(value_type, value)?))
+        }
+        _ => return Err(None),
+    })
+}
+
+fn parse_string(value: &str) -> std::result::Result<String, Option<String>> {
+    match value.strip_prefix("Binary{\"").and_then(|v| v.strip_suffix("\"}")) {
+        Some(s) => Ok(s.to_string()),
+        None => String::from_utf8(parse_bytes(value)?).map_err(|e| Some(e.to_string())),
+    }
+}
+
+fn parse_bytes(value: &str) -> std::result::Result<Vec<u8>, Option<String>> {
+    if let Some(s) = value.strip_prefix("Binary{\"").and_then(|v| v.strip_suffix("\"}")) {
+        return Ok(s.as_bytes().to_vec());
+    }
+    parse_binary_array(value)
+        .map_err(Some)?
+        .ok_or_else(|| Some("expected Binary{...}".to_string()))
+}
+
+// unscaled value of decimals, either an integer or big-endian two's complement bytes
+fn parse_decimal(value: &str) -> std::result::Result<i128, Option<String>> {
+    if !value.contains('[') {
+        return value.parse::<i128>().map_err(|e| Some(e.to_string()));
+    }
+    let bytes = parse_bytes(value)?;
+    let negative = bytes.first().map_or(false, |b| b & 0x80 != 0);
+    let fill = if negative { 0xFF } else { 0 };
+    let mut arr = [fill; 16];
+    if bytes.len() > 16 {
+        let (high, low) = bytes.split_at(bytes.len() - 16);
+        if high.iter().any(|b| *b != fill) || (low[0] & 0x80 != 0) != negative {
+            return Err(Some("out of range of 128-bit decimal".to_string()));
         }
+        arr.copy_from_slice(low);
+    } else {
+        arr[16 - bytes.len()..].copy_from_slice(&bytes);
+    }
+    Ok(i128::from_be_bytes(arr))
+}
+
+// bytes encoded like Binary{3 constant bytes, [1, 2, -1]}
+fn parse_binary_array(value: &str) -> std::result::Result<Option<Vec<u8>>, String> {
+    let left_bracket_pos = value.find('[').unwrap_or(0);
+    let right_bracket_pos = value.find(']').unwrap_or(0);
+    if left_bracket_pos == 0 {
+        Ok(None)
+    } else if left_bracket_pos + 1 == right_bracket_pos {
+        Ok(Some(Vec::<u8>::new()))
+    } else if right_bracket_pos < left_bracket_pos {
+        Err("unclosed bytes".to_string())
+    } else {
+        value[left_bracket_pos + 1..right_bracket_pos]
+            .split(',')
+            .map(|s| {
+                s.trim()
+                    .parse::<i16>()
+                    .map_err(|e| e.to_string())
+                    .map(|s: i16| if s < 0 { (s + 256) as u8 } else { s as u8 })
+            })
+            .collect::<std::result::Result<Vec<u8>, String>>()
+            .map(Some)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::filter::Parser;
-    use std::result::Result;
+    use super::*;
+    use arrow_schema::Schema;
+    use datafusion::common::Column;
+    use datafusion::logical_expr::GetIndexedField;
+    use std::sync::Arc;
+
+    fn test_schema() -> SchemaRef {
+        let entries = Field::new(
+            "entries",
+            DataType::Struct(vec![
+                Field::new("key", DataType::Utf8, false),
+                Field::new("value", DataType::Int64, true),
+            ]),
+            false,
+        );
+        Arc::new(Schema::new(vec![
+            Field::new("a.b.c", DataType::Float64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("id", DataType::Int32, true),
+            Field::new(
+                "s",
+                DataType::Struct(vec![
+                    Field::new("x", DataType::Int32, true),
+                    Field::new("m", DataType::Map(Box::new(entries), false), true),
+                ]),
+                true,
+            ),
+        ]))
+    }
 
     #[test]
-    fn test_filter_parser() -> Result<(), String> {
+    fn test_filter_parser() -> ParseResult<()> {
         let s = String::from("or(lt(a.b.c, 2.0), gt(a.b.c, 3.0))");
-        let (op, left, right) = Parser::parse_filter_str(s);
-        assert_eq!(op, "or");
-        assert_eq!(left, "lt(a.b.c, 2.0)");
-        assert_eq!(right, "gt(a.b.c, 3.0)");
+        let expr = Parser::parse(s, test_schema())?;
+        let column = Expr::Column(Column::from_name("a.b.c"));
+        let expected = column
+            .clone()
+            .lt(Expr::Literal(ScalarValue::Float64(Some(2.0))))
+            .or(column.gt(Expr::Literal(ScalarValue::Float64(Some(3.0)))));
+        assert_eq!(expr, expected);
+
+        let s = String::from("and(eq(name, Binary{\"a, (b)\"}), not(eq(id, null)))");
+        let expr = Parser::parse(s, test_schema())?;
+        let expected = Expr::Column(Column::from_name("name"))
+            .eq(Expr::Literal(ScalarValue::Utf8(Some("a, (b)".to_string()))))
+            .and(Expr::not(Expr::Column(Column::from_name("id")).is_null()));
+        assert_eq!(expr, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_parser_operators() -> ParseResult<()> {
+        let parse = |s: &str| Parser::parse(s.to_string(), test_schema());
+        let id = || Expr::Column(Column::from_name("id"));
+        let name = || Expr::Column(Column::from_name("name"));
+        let int = |v: i32| Expr::Literal(ScalarValue::Int32(Some(v)));
+        let utf8 = |v: &str| Expr::Literal(ScalarValue::Utf8(Some(v.to_string())));
+
+        assert_eq!(
+            parse("in(id, [1, 2, null])")?,
+            id().in_list(vec![int(1), int(2), Expr::Literal(ScalarValue::Int32(None))], false)
+        );
+        assert_eq!(
+            parse("notIn(name, [Binary{\"a, b\"}, Binary{\"c]\"}])")?,
+            name().in_list(vec![utf8("a, b"), utf8("c]")], true)
+        );
+        assert_eq!(parse("startsWith(name, Binary{\"a%\"})")?, name().like(utf8("a\\%%")));
+        assert_eq!(parse("contains(name, Binary{\"b\"})")?, name().like(utf8("%b%")));
+        assert_eq!(parse("like(name, Binary{\"a_c%\"})")?, name().like(utf8("a_c%")));
+        assert_eq!(
+            parse("eqNullSafe(id, 1)")?,
+            Expr::BinaryExpr(BinaryExpr {
+                left: Box::new(id()),
+                op: Operator::IsNotDistinctFrom,
+                right: Box::new(int(1)),
+            })
+        );
+        assert_eq!(parse("eqNullSafe(id, null)")?, id().is_null());
+        assert_eq!(
+            parse("between(id, 1, 10)")?,
+            Expr::Between(Between {
+                expr: Box::new(id()),
+                negated: false,
+                low: Box::new(int(1)),
+                high: Box::new(int(10)),
+            })
+        );
+        assert!(matches!(parse("isNaN(a.b.c)")?, Expr::ScalarUDF { .. }));
+        assert!(matches!(
+            parse("isNaN(id)").unwrap_err().kind,
+            ParseErrorKind::UnsupportedType { .. }
+        ));
+        assert!(matches!(
+            parse("contains(id, 1)").unwrap_err().kind,
+            ParseErrorKind::UnsupportedType { .. }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_literal() -> ParseResult<()> {
+        let parse = |data_type: DataType, value: &str| parse_literal(&Field::new("c", data_type, true), value, 0);
+
+        assert_eq!(
+            parse(
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_string())),
+                "1000"
+            )?,
+            ScalarValue::TimestampMillisecond(Some(1000), Some("UTC".to_string()))
+        );
+        assert_eq!(
+            parse(DataType::Timestamp(TimeUnit::Nanosecond, None), "1")?,
+            ScalarValue::TimestampNanosecond(Some(1), None)
+        );
+        assert_eq!(
+            parse(DataType::Date64, "86400000")?,
+            ScalarValue::Date64(Some(86400000))
+        );
+        assert_eq!(
+            parse(DataType::UInt64, "18446744073709551615")?,
+            ScalarValue::UInt64(Some(u64::MAX))
+        );
+        assert_eq!(
+            parse(DataType::Time64(TimeUnit::Microsecond), "5")?,
+            ScalarValue::Time64Microsecond(Some(5))
+        );
+        assert_eq!(
+            parse(DataType::LargeUtf8, "Binary{\"abc\"}")?,
+            ScalarValue::LargeUtf8(Some("abc".to_string()))
+        );
+        assert_eq!(
+            parse(DataType::Utf8, "Binary{2 constant bytes, [104, 105]}")?,
+            ScalarValue::Utf8(Some("hi".to_string()))
+        );
+        assert_eq!(
+            parse(DataType::FixedSizeBinary(2), "Binary{2 constant bytes, [1, -1]}")?,
+            ScalarValue::FixedSizeBinary(2, Some(vec![1, 255]))
+        );
+        assert_eq!(
+            parse(DataType::Decimal128(20, 2), "Binary{2 constant bytes, [-1, -2]}")?,
+            ScalarValue::Decimal128(Some(-2), 20, 2)
+        );
+        assert_eq!(
+            parse(DataType::Decimal256(40, 2), "12345")?,
+            ScalarValue::Decimal128(Some(12345), 38, 2)
+        );
+
+        let err = parse(DataType::FixedSizeBinary(3), "Binary{2 constant bytes, [1, 2]}").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidLiteral { .. }));
+        let err = parse(DataType::Int32, "Binary{\"abc\"}").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidLiteral { .. }));
+        let err = parse(DataType::UInt8, "-1").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidLiteral { .. }));
+        let err = parse(DataType::Interval(arrow_schema::IntervalUnit::DayTime), "1").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnsupportedType { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_parser_errors() {
+        let parse = |s: &str| Parser::parse(s.to_string(), test_schema()).unwrap_err();
+
+        let err = parse("gt(id, 1");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd);
+
+        let err = parse("regexp(id, 1)");
+        assert_eq!(err.position, 0);
+        assert_eq!(err.kind, ParseErrorKind::UnknownOperator("regexp".to_string()));
+
+        let err = parse("and(eq(id, 1), eq(foo, 1))");
+        assert_eq!(err.position, 18);
+        assert_eq!(err.kind, ParseErrorKind::UnknownColumn("foo".to_string()));
+
+        let err = parse("eq(id, abc)");
+        assert_eq!(err.position, 7);
+        assert!(matches!(err.kind, ParseErrorKind::InvalidLiteral { .. }));
+
+        let err = parse("eq(id, 1))");
+        assert_eq!(err.position, 9);
+        assert_eq!(err.kind, ParseErrorKind::TrailingCharacters);
+
+        let err = parse("eq id, 1)");
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::Expected {
+                expected: "'('".to_string(),
+                found: 'i'
+            }
+        );
+    }
+
+    #[test]
+    fn test_filter_parser_nested_columns() -> ParseResult<()> {
+        let expr = Parser::parse("gt(s.x, 1)".to_string(), test_schema())?;
+        let expected = Expr::GetIndexedField(GetIndexedField::new(
+            Box::new(Expr::Column(Column::from_name("s"))),
+            ScalarValue::Utf8(Some("x".to_string())),
+        ))
+        .gt(Expr::Literal(ScalarValue::Int32(Some(1))));
+        assert_eq!(expr, expected);
+
+        // map values are typed as the value type of the map
+        let expr = Parser::parse("eq(s.m['a, b'], 2)".to_string(), test_schema())?;
+        match expr {
+            Expr::BinaryExpr(BinaryExpr { right, .. }) => {
+                assert_eq!(*right, Expr::Literal(ScalarValue::Int64(Some(2))))
+            }
+            _ => panic!("unexpected expr {:?}", expr),
+        }
+
+        let err = Parser::parse("eq(s.y, 1)".to_string(), test_schema()).unwrap_err();
+        assert_eq!(err.position, 3);
+        assert_eq!(err.kind, ParseErrorKind::UnknownColumn("s.y".to_string()));
         Ok(())
     }
 }
@@ -0,0 +1,154 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use arrow_schema::SchemaRef;
+use datafusion::datasource::empty::EmptyTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::expr_rewriter::unnormalize_col;
+use datafusion::logical_expr::{Expr, LogicalPlan};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::Parser;
+use datafusion::sql::sqlparser::tokenizer::Token;
+use std::sync::Arc;
+
+const TABLE_NAME: &str = "lakesoul_filter_table";
+
+/// Parse a SQL boolean expression like `region = 'cn' AND ts > TIMESTAMP '2023-01-01'`
+/// by DataFusion's SQL planner. Unquoted column names are lowercased as in DataFusion SQL.
+pub fn parse_sql_filter(sql: &str, schema: SchemaRef) -> Result<Expr> {
+    let ctx = planning_context(sql, schema)?;
+    // planned as the where clause of a query
+    let df = futures::executor::block_on(ctx.sql(&format!("SELECT * FROM {} WHERE {}", TABLE_NAME, sql)))?;
+    // optimized to coerce types of literals to column types
+    let plan = ctx.state().optimize(df.logical_plan())?;
+    match find_predicate(&plan) {
+        Some(predicate) => Ok(unnormalize_col(predicate)),
+        // predicates simplified to false
+        None if matches!(plan, LogicalPlan::EmptyRelation(_)) => Ok(Expr::Literal(false.into())),
+        // predicates simplified to true
+        None => Ok(Expr::Literal(true.into())),
+    }
+}
+
+/// Parse a SQL scalar expression like `coalesce(a, 0) + 1` by DataFusion's SQL planner.
+/// Types are not coerced, which is left to be done against the schema it's evaluated on.
+pub fn parse_sql_expr(sql: &str, schema: SchemaRef) -> Result<Expr> {
+    let ctx = planning_context(sql, schema)?;
+    // planned as the only item of select list
+    let df = futures::executor::block_on(ctx.sql(&format!("SELECT {} FROM {}", sql, TABLE_NAME)))?;
+    match df.logical_plan() {
+        LogicalPlan::Projection(projection) if projection.expr.len() == 1 => {
+            Ok(unnormalize_col(projection.expr[0].clone().unalias()))
+        }
+        plan => Err(DataFusionError::Plan(format!(
+            "{} is not a scalar expression, planned as {:?}",
+            sql, plan
+        ))),
+    }
+}
+
+// Session with a table of schema to plan the expression in queries,
+// the expression must be a single one so that it could not change the query
+fn planning_context(sql: &str, schema: SchemaRef) -> Result<SessionContext> {
+    let mut parser = Parser::new(&GenericDialect {}).try_with_sql(sql)?;
+    parser.parse_expr()?;
+    if parser.peek_token() != Token::EOF {
+        return Err(DataFusionError::Plan(format!(
+            "unexpected {} after expression",
+            parser.peek_token()
+        )));
+    }
+    let ctx = SessionContext::new();
+    ctx.register_table(TABLE_NAME, Arc::new(EmptyTable::new(schema)))?;
+    Ok(ctx)
+}
+
+fn find_predicate(plan: &LogicalPlan) -> Option<Expr> {
+    match plan {
+        LogicalPlan::Filter(filter) => Some(filter.predicate.clone()),
+        plan => plan.inputs().into_iter().find_map(find_predicate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{as_boolean_array, ArrayRef, Int32Array, StringArray, TimestampMicrosecondArray};
+    use arrow::record_batch::RecordBatch;
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::common::{Column, DFSchema};
+    use datafusion::logical_expr::utils::expr_to_columns;
+    use datafusion::physical_expr::create_physical_expr;
+    use datafusion::physical_expr::execution_props::ExecutionProps;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_sql_filter() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "region",
+                Arc::new(StringArray::from(vec!["cn", "cn", "us"])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    1_640_995_200_000_000,
+                    1_704_067_200_000_000,
+                    1_704_067_200_000_000,
+                ])) as ArrayRef,
+            ),
+        ])?;
+        let schema = batch.schema();
+        let filter = parse_sql_filter(
+            "region = 'cn' AND ts > TIMESTAMP '2023-01-01' AND id < 10",
+            schema.clone(),
+        )?;
+        let df_schema = DFSchema::try_from(schema.as_ref().clone())?;
+        let predicate = create_physical_expr(&filter, &df_schema, &schema, &ExecutionProps::new())?;
+        let result = predicate.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = as_boolean_array(&result);
+        assert_eq!(
+            result.iter().collect::<Vec<_>>(),
+            vec![Some(false), Some(true), Some(false)]
+        );
+
+        assert!(parse_sql_filter("id = 1; DROP TABLE t", schema.clone()).is_err());
+        assert!(parse_sql_filter("id = 1 UNION SELECT 1", schema.clone()).is_err());
+        assert!(parse_sql_filter("unknown = 1", schema).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_sql_expr() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("v", DataType::Int64, true),
+        ]));
+        let expr = parse_sql_expr("coalesce(v, 0) + id AS x", schema.clone());
+        assert!(expr.is_err());
+        let expr = parse_sql_expr("coalesce(v, 0) + id", schema.clone())?;
+        let mut columns = HashSet::new();
+        expr_to_columns(&expr, &mut columns)?;
+        assert_eq!(
+            columns,
+            HashSet::from([Column::from_name("id"), Column::from_name("v")])
+        );
+        assert!(parse_sql_expr("id, v", schema).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,630 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::column_path::{ColumnPath, PathSegment};
+use crate::filter::parser::{escape_like_pattern, is_nan_udf};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow_schema::{DataType, SchemaRef, TimeUnit};
+use datafusion::common::DFSchema;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, ExprSchemable, Operator};
+use datafusion::scalar::ScalarValue;
+use prost::Message;
+use std::collections::HashMap;
+use substrait::proto::expression::field_reference::ReferenceType;
+use substrait::proto::expression::literal::LiteralType;
+use substrait::proto::expression::reference_segment;
+use substrait::proto::expression::{FieldReference, Literal, RexType, ScalarFunction};
+use substrait::proto::expression_reference::ExprType;
+use substrait::proto::extensions::simple_extension_declaration::MappingType;
+use substrait::proto::function_argument::ArgType;
+use substrait::proto::r#type::Kind;
+use substrait::proto::{Expression, ExtendedExpression, NamedStruct, Type};
+
+/// An expression decoded from Substrait
+#[derive(Debug, Clone)]
+pub struct DecodedExpr {
+    pub expr: Expr,
+    /// path of the column if the expression is a field reference
+    pub column: Option<String>,
+    /// first output name given to the expression
+    pub output_name: Option<String>,
+}
+
+/// Decode expressions of a serialized Substrait `ExtendedExpression`. Field references are
+/// resolved by names of its base schema, or by positions in schema if there's no base schema.
+pub fn decode_extended_expression(bytes: &[u8], schema: SchemaRef) -> Result<Vec<DecodedExpr>> {
+    let extended = ExtendedExpression::decode(bytes)
+        .map_err(|e| DataFusionError::Plan(format!("invalid substrait extended expression: {}", e)))?;
+    let columns = match &extended.base_schema {
+        Some(base_schema) => top_level_names(base_schema)?,
+        None => schema.fields().iter().map(|f| f.name().clone()).collect(),
+    };
+    let functions = extended
+        .extensions
+        .iter()
+        .filter_map(|ext| match &ext.mapping_type {
+            // names may have signatures like `equal:any_any`
+            Some(MappingType::ExtensionFunction(f)) => Some((
+                f.function_anchor,
+                f.name.split(':').next().unwrap_or_default().to_string(),
+            )),
+            _ => None,
+        })
+        .collect();
+    let decoder = SubstraitDecoder {
+        df_schema: DFSchema::try_from(schema.as_ref().clone())?,
+        schema,
+        columns,
+        functions,
+    };
+    extended
+        .referred_expr
+        .iter()
+        .map(|referred| match &referred.expr_type {
+            Some(ExprType::Expression(expression)) => {
+                let column = match &expression.rex_type {
+                    Some(RexType::Selection(reference)) => Some(decoder.column_path(reference)?.to_string()),
+                    _ => None,
+                };
+                Ok(DecodedExpr {
+                    expr: decoder.expression(expression)?,
+                    column,
+                    output_name: referred.output_names.first().cloned(),
+                })
+            }
+            _ => Err(DataFusionError::NotImplemented(
+                "only scalar expressions of substrait are supported".to_string(),
+            )),
+        })
+        .collect()
+}
+
+// Names of top-level fields, names of a NamedStruct include nested fields in depth-first order
+fn top_level_names(base_schema: &NamedStruct) -> Result<Vec<String>> {
+    let types = match &base_schema.r#struct {
+        Some(s) => &s.types,
+        None => return Ok(base_schema.names.clone()),
+    };
+    let mut names = base_schema.names.iter();
+    let mut top_level = Vec::with_capacity(types.len());
+    for t in types {
+        let name = names
+            .next()
+            .ok_or_else(|| DataFusionError::Plan("missing names in substrait base schema".to_string()))?;
+        top_level.push(name.clone());
+        for _ in 0..nested_name_count(t) {
+            names.next();
+        }
+    }
+    Ok(top_level)
+}
+
+fn nested_name_count(t: &Type) -> usize {
+    match &t.kind {
+        Some(Kind::Struct(s)) => s.types.iter().map(|t| 1 + nested_name_count(t)).sum(),
+        Some(Kind::List(l)) => l.r#type.as_deref().map_or(0, nested_name_count),
+        Some(Kind::Map(m)) => {
+            m.key.as_deref().map_or(0, nested_name_count) + m.value.as_deref().map_or(0, nested_name_count)
+        }
+        _ => 0,
+    }
+}
+
+struct SubstraitDecoder {
+    schema: SchemaRef,
+    df_schema: DFSchema,
+    // names of columns referenced by positions
+    columns: Vec<String>,
+    // function names by anchors
+    functions: HashMap<u32, String>,
+}
+
+impl SubstraitDecoder {
+    fn expression(&self, expression: &Expression) -> Result<Expr> {
+        match &expression.rex_type {
+            Some(RexType::Literal(lit)) => Ok(Expr::Literal(literal(lit)?)),
+            Some(RexType::Selection(reference)) => {
+                let (expr, _) = self.column_path(reference)?.expr(&self.schema)?;
+                Ok(expr)
+            }
+            Some(RexType::ScalarFunction(function)) => self.scalar_function(function),
+            Some(RexType::SingularOrList(in_list)) => {
+                let value = in_list
+                    .value
+                    .as_deref()
+                    .ok_or_else(|| DataFusionError::Plan("missing value of substrait in list".to_string()))?;
+                let value = self.expression(value)?;
+                let list = in_list
+                    .options
+                    .iter()
+                    .map(|option| self.coerce_to(&value, self.expression(option)?))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(value.in_list(list, false))
+            }
+            rex_type => Err(DataFusionError::NotImplemented(format!(
+                "unsupported substrait expression {:?}",
+                rex_type
+            ))),
+        }
+    }
+
+    // Path of a direct field reference like `a.b[1]`, built by segments so that names
+    // and map keys are taken as they are
+    fn column_path(&self, reference: &FieldReference) -> Result<ColumnPath> {
+        let mut segment = match &reference.reference_type {
+            Some(ReferenceType::DirectReference(segment)) => segment,
+            _ => {
+                return Err(DataFusionError::NotImplemented(
+                    "only direct field references of substrait are supported".to_string(),
+                ))
+            }
+        };
+        let mut path: Option<ColumnPath> = None;
+        let mut data_type: Option<DataType> = None;
+        loop {
+            let (next, child) = match (&segment.reference_type, path) {
+                (Some(reference_segment::ReferenceType::StructField(field)), None) => {
+                    let root = self
+                        .columns
+                        .get(field.field as usize)
+                        .ok_or_else(|| DataFusionError::Plan(format!("invalid field {}", field.field)))?;
+                    let path = ColumnPath {
+                        root: root.clone(),
+                        segments: vec![],
+                    };
+                    (path, &field.child)
+                }
+                (Some(reference_segment::ReferenceType::StructField(field)), Some(mut path)) => {
+                    let name = match &data_type {
+                        Some(DataType::Struct(fields)) => fields.get(field.field as usize).map(|f| f.name().clone()),
+                        _ => None,
+                    }
+                    .ok_or_else(|| DataFusionError::Plan(format!("invalid field {} of {}", field.field, path)))?;
+                    path.segments.push(PathSegment::Field(name));
+                    (path, &field.child)
+                }
+                // substrait offsets are 0-based while DataFusion indexes are 1-based
+                (Some(reference_segment::ReferenceType::ListElement(element)), Some(mut path)) => {
+                    path.segments.push(PathSegment::Index(element.offset as i64 + 1));
+                    (path, &element.child)
+                }
+                (Some(reference_segment::ReferenceType::MapKey(map_key)), Some(mut path)) => {
+                    let key = map_key
+                        .map_key
+                        .as_ref()
+                        .ok_or_else(|| DataFusionError::Plan("missing substrait map key".to_string()))?;
+                    path.segments.push(PathSegment::Key(literal(key)?.to_string()));
+                    (path, &map_key.child)
+                }
+                (segment, _) => {
+                    return Err(DataFusionError::Plan(format!(
+                        "invalid substrait reference segment {:?}",
+                        segment
+                    )))
+                }
+            };
+            data_type = Some(next.expr(&self.schema)?.1);
+            match child {
+                Some(child) => {
+                    segment = child.as_ref();
+                    path = Some(next);
+                }
+                None => return Ok(next),
+            }
+        }
+    }
+
+    fn scalar_function(&self, function: &ScalarFunction) -> Result<Expr> {
+        let name = self
+            .functions
+            .get(&function.function_reference)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "unknown substrait function reference {}",
+                    function.function_reference
+                ))
+            })?
+            .as_str();
+        let mut args = function
+            .arguments
+            .iter()
+            .map(|arg| match &arg.arg_type {
+                Some(ArgType::Value(value)) => self.expression(value),
+                arg_type => Err(DataFusionError::NotImplemented(format!(
+                    "unsupported argument {:?} of substrait function {}",
+                    arg_type, name
+                ))),
+            })
+            .collect::<Result<Vec<Expr>>>()?;
+        let expect_args = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(DataFusionError::Plan(format!(
+                    "substrait function {} expects {} arguments, got {}",
+                    name,
+                    n,
+                    args.len()
+                )))
+            }
+        };
+        match name {
+            "and" | "or" => {
+                let op = if name == "and" { Operator::And } else { Operator::Or };
+                args.into_iter()
+                    .reduce(|a, b| Expr::BinaryExpr(BinaryExpr::new(Box::new(a), op, Box::new(b))))
+                    .ok_or_else(|| DataFusionError::Plan(format!("substrait function {} without arguments", name)))
+            }
+            "not" | "is_null" | "is_not_null" | "is_nan" => {
+                expect_args(1)?;
+                let arg = args.remove(0);
+                Ok(match name {
+                    "not" => Expr::Not(Box::new(arg)),
+                    "is_null" => arg.is_null(),
+                    "is_not_null" => arg.is_not_null(),
+                    _ => is_nan_udf().call(vec![arg]),
+                })
+            }
+            "equal" | "not_equal" | "lt" | "lte" | "gt" | "gte" | "is_not_distinct_from" | "is_distinct_from" => {
+                expect_args(2)?;
+                let right = args.pop().unwrap();
+                let left = args.pop().unwrap();
+                let (left, right) = (self.coerce_to(&right, left)?, self.coerce_to(&left, right)?);
+                let op = match name {
+                    "equal" => Operator::Eq,
+                    "not_equal" => Operator::NotEq,
+                    "lt" => Operator::Lt,
+                    "lte" => Operator::LtEq,
+                    "gt" => Operator::Gt,
+                    "gte" => Operator::GtEq,
+                    "is_not_distinct_from" => Operator::IsNotDistinctFrom,
+                    _ => Operator::IsDistinctFrom,
+                };
+                Ok(Expr::BinaryExpr(BinaryExpr::new(Box::new(left), op, Box::new(right))))
+            }
+            "between" => {
+                expect_args(3)?;
+                let high = args.pop().unwrap();
+                let low = args.pop().unwrap();
+                let expr = args.pop().unwrap();
+                Ok(Expr::Between(Between::new(
+                    Box::new(expr.clone()),
+                    false,
+                    Box::new(self.coerce_to(&expr, low)?),
+                    Box::new(self.coerce_to(&expr, high)?),
+                )))
+            }
+            "like" | "starts_with" | "ends_with" | "contains" => {
+                expect_args(2)?;
+                let pattern = args.pop().unwrap();
+                let expr = args.pop().unwrap();
+                let pattern = match (name, pattern) {
+                    ("like", pattern) => pattern,
+                    (_, Expr::Literal(ScalarValue::Utf8(Some(s)))) => {
+                        let s = escape_like_pattern(&s);
+                        let pattern = match name {
+                            "starts_with" => format!("{}%", s),
+                            "ends_with" => format!("%{}", s),
+                            _ => format!("%{}%", s),
+                        };
+                        Expr::Literal(ScalarValue::Utf8(Some(pattern)))
+                    }
+                    (_, pattern) => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "substrait function {} only supports string literal patterns, got {:?}",
+                            name, pattern
+                        )))
+                    }
+                };
+                Ok(expr.like(pattern))
+            }
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "unsupported substrait function {}",
+                name
+            ))),
+        }
+    }
+
+    // Literals compared with other expressions are cast to their types,
+    // since physical expressions are created without type coercion
+    fn coerce_to(&self, other: &Expr, expr: Expr) -> Result<Expr> {
+        match (&expr, other) {
+            (Expr::Literal(value), other) if !matches!(other, Expr::Literal(_)) => {
+                let data_type = other.get_type(&self.df_schema)?;
+                if value.get_datatype() == data_type {
+                    return Ok(expr);
+                }
+                // literals not fitting the type are rejected instead of turned into nulls
+                let array =
+                    cast_with_options(&value.to_array(), &data_type, &CastOptions { safe: false }).map_err(|e| {
+                        DataFusionError::Plan(format!("literal {} could not be cast to {:?}: {}", value, data_type, e))
+                    })?;
+                Ok(Expr::Literal(ScalarValue::try_from_array(&array, 0)?))
+            }
+            _ => Ok(expr),
+        }
+    }
+}
+
+fn literal(lit: &Literal) -> Result<ScalarValue> {
+    let value = match &lit.literal_type {
+        Some(LiteralType::Boolean(v)) => ScalarValue::Boolean(Some(*v)),
+        Some(LiteralType::I8(v)) => ScalarValue::Int8(Some(*v as i8)),
+        Some(LiteralType::I16(v)) => ScalarValue::Int16(Some(*v as i16)),
+        Some(LiteralType::I32(v)) => ScalarValue::Int32(Some(*v)),
+        Some(LiteralType::I64(v)) => ScalarValue::Int64(Some(*v)),
+        Some(LiteralType::Fp32(v)) => ScalarValue::Float32(Some(*v)),
+        Some(LiteralType::Fp64(v)) => ScalarValue::Float64(Some(*v)),
+        Some(LiteralType::String(v)) | Some(LiteralType::FixedChar(v)) => ScalarValue::Utf8(Some(v.clone())),
+        Some(LiteralType::VarChar(v)) => ScalarValue::Utf8(Some(v.value.clone())),
+        Some(LiteralType::Binary(v)) => ScalarValue::Binary(Some(v.clone())),
+        Some(LiteralType::FixedBinary(v)) => ScalarValue::FixedSizeBinary(v.len() as i32, Some(v.clone())),
+        Some(LiteralType::Date(v)) => ScalarValue::Date32(Some(*v)),
+        Some(LiteralType::Time(v)) => ScalarValue::Time64Microsecond(Some(*v)),
+        Some(LiteralType::Timestamp(v)) => ScalarValue::TimestampMicrosecond(Some(*v), None),
+        Some(LiteralType::TimestampTz(v)) => ScalarValue::TimestampMicrosecond(Some(*v), Some("UTC".to_string())),
+        Some(LiteralType::Decimal(d)) => {
+            // 16 bytes of little-endian two's complement
+            let bytes: [u8; 16] =
+                d.value.as_slice().try_into().map_err(|_| {
+                    DataFusionError::Plan(format!("invalid substrait decimal of {} bytes", d.value.len()))
+                })?;
+            ScalarValue::Decimal128(Some(i128::from_le_bytes(bytes)), d.precision as u8, d.scale as i8)
+        }
+        Some(LiteralType::Null(t)) => ScalarValue::try_from(&data_type(t)?)?,
+        literal_type => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "unsupported substrait literal {:?}",
+                literal_type
+            )))
+        }
+    };
+    Ok(value)
+}
+
+// arrow type of substrait type, for typed null literals
+fn data_type(t: &Type) -> Result<DataType> {
+    let data_type = match &t.kind {
+        Some(Kind::Bool(_)) => DataType::Boolean,
+        Some(Kind::I8(_)) => DataType::Int8,
+        Some(Kind::I16(_)) => DataType::Int16,
+        Some(Kind::I32(_)) => DataType::Int32,
+        Some(Kind::I64(_)) => DataType::Int64,
+        Some(Kind::Fp32(_)) => DataType::Float32,
+        Some(Kind::Fp64(_)) => DataType::Float64,
+        Some(Kind::String(_)) | Some(Kind::FixedChar(_)) | Some(Kind::Varchar(_)) => DataType::Utf8,
+        Some(Kind::Binary(_)) => DataType::Binary,
+        Some(Kind::Date(_)) => DataType::Date32,
+        Some(Kind::Time(_)) => DataType::Time64(TimeUnit::Microsecond),
+        Some(Kind::Timestamp(_)) => DataType::Timestamp(TimeUnit::Microsecond, None),
+        Some(Kind::TimestampTz(_)) => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".to_string())),
+        Some(Kind::Decimal(d)) => DataType::Decimal128(d.precision as u8, d.scale as i8),
+        kind => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "unsupported substrait type {:?}",
+                kind
+            )))
+        }
+    };
+    Ok(data_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_schema::{Field, Schema};
+    use datafusion::common::Column;
+    use std::sync::Arc;
+    use substrait::proto::expression::reference_segment::{MapKey, StructField};
+    use substrait::proto::expression::{ReferenceSegment, SingularOrList};
+    use substrait::proto::extensions::simple_extension_declaration::ExtensionFunction;
+    use substrait::proto::extensions::SimpleExtensionDeclaration;
+    use substrait::proto::{ExpressionReference, FunctionArgument};
+
+    fn test_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new(
+                "s",
+                DataType::Struct(vec![Field::new("x", DataType::Int64, true)]),
+                true,
+            ),
+            Field::new(
+                "m",
+                DataType::Map(
+                    Box::new(Field::new(
+                        "entries",
+                        DataType::Struct(vec![
+                            Field::new("key", DataType::Utf8, false),
+                            Field::new("value", DataType::Int32, true),
+                        ]),
+                        false,
+                    )),
+                    false,
+                ),
+                true,
+            ),
+        ]))
+    }
+
+    fn field(idx: i32, child: Option<i32>) -> Expression {
+        let child = child.map(|c| {
+            Box::new(ReferenceSegment {
+                reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(StructField {
+                    field: c,
+                    child: None,
+                }))),
+            })
+        });
+        Expression {
+            rex_type: Some(RexType::Selection(Box::new(FieldReference {
+                reference_type: Some(ReferenceType::DirectReference(ReferenceSegment {
+                    reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(StructField {
+                        field: idx,
+                        child,
+                    }))),
+                })),
+                root_type: None,
+            }))),
+        }
+    }
+
+    fn lit(literal_type: LiteralType) -> Expression {
+        Expression {
+            rex_type: Some(RexType::Literal(Literal {
+                literal_type: Some(literal_type),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn call(anchor: u32, args: Vec<Expression>) -> Expression {
+        Expression {
+            rex_type: Some(RexType::ScalarFunction(ScalarFunction {
+                function_reference: anchor,
+                arguments: args
+                    .into_iter()
+                    .map(|arg| FunctionArgument {
+                        arg_type: Some(ArgType::Value(arg)),
+                    })
+                    .collect(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn encode(exprs: Vec<Expression>) -> Vec<u8> {
+        let functions = ["and:bool", "gt:any_any", "starts_with:string_string"];
+        ExtendedExpression {
+            extensions: functions
+                .iter()
+                .enumerate()
+                .map(|(anchor, name)| SimpleExtensionDeclaration {
+                    mapping_type: Some(MappingType::ExtensionFunction(ExtensionFunction {
+                        function_anchor: anchor as u32,
+                        name: name.to_string(),
+                        ..Default::default()
+                    })),
+                })
+                .collect(),
+            referred_expr: exprs
+                .into_iter()
+                .map(|expr| ExpressionReference {
+                    expr_type: Some(ExprType::Expression(expr)),
+                    output_names: vec![],
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_decode_extended_expression() -> Result<()> {
+        let filter = call(
+            0,
+            vec![
+                // literals are cast to column types
+                call(1, vec![field(0, None), lit(LiteralType::I64(1))]),
+                call(2, vec![field(1, None), lit(LiteralType::String("a%".to_string()))]),
+                Expression {
+                    rex_type: Some(RexType::SingularOrList(Box::new(SingularOrList {
+                        value: Some(Box::new(field(2, Some(0)))),
+                        options: vec![lit(LiteralType::I32(1)), lit(LiteralType::I32(2))],
+                    }))),
+                },
+            ],
+        );
+        let decoded = decode_extended_expression(&encode(vec![filter, field(2, Some(0))]), test_schema())?;
+        assert_eq!(decoded.len(), 2);
+
+        let id = Expr::Column(Column::from_name("id"));
+        let name = Expr::Column(Column::from_name("name"));
+        let (_, x, _) = ColumnPath::resolve("s.x", &test_schema())?;
+        let expected = id
+            .gt(Expr::Literal(ScalarValue::Int32(Some(1))))
+            .and(name.like(Expr::Literal(ScalarValue::Utf8(Some("a\\%%".to_string())))))
+            .and(x.clone().in_list(
+                vec![
+                    Expr::Literal(ScalarValue::Int64(Some(1))),
+                    Expr::Literal(ScalarValue::Int64(Some(2))),
+                ],
+                false,
+            ));
+        assert_eq!(decoded[0].expr, expected);
+        assert_eq!(decoded[0].column, None);
+        assert_eq!(decoded[1].expr, x);
+        assert_eq!(decoded[1].column, Some("s.x".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_map_key() -> Result<()> {
+        // keys are taken as they are, even with quotes and brackets
+        let key = "it's]".to_string();
+        let reference = Expression {
+            rex_type: Some(RexType::Selection(Box::new(FieldReference {
+                reference_type: Some(ReferenceType::DirectReference(ReferenceSegment {
+                    reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(StructField {
+                        field: 3,
+                        child: Some(Box::new(ReferenceSegment {
+                            reference_type: Some(reference_segment::ReferenceType::MapKey(Box::new(MapKey {
+                                map_key: Some(Literal {
+                                    literal_type: Some(LiteralType::String(key.clone())),
+                                    ..Default::default()
+                                }),
+                                child: None,
+                            }))),
+                        })),
+                    }))),
+                })),
+                root_type: None,
+            }))),
+        };
+        let decoded = decode_extended_expression(&encode(vec![reference]), test_schema())?;
+        let path = ColumnPath {
+            root: "m".to_string(),
+            segments: vec![PathSegment::Key(key)],
+        };
+        assert_eq!(decoded[0].expr, path.expr(&test_schema())?.0);
+        assert_eq!(decoded[0].column, Some(path.to_string()));
+        let (resolved, _, _) = ColumnPath::resolve(&path.to_string(), &test_schema())?;
+        assert_eq!(resolved, path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode_extended_expression(b"not a message", test_schema()).is_err());
+        // unknown function anchor
+        let bytes = encode(vec![call(9, vec![field(0, None)])]);
+        assert!(decode_extended_expression(&bytes, test_schema()).is_err());
+        // out of range field
+        let bytes = encode(vec![field(5, None)]);
+        assert!(decode_extended_expression(&bytes, test_schema()).is_err());
+        // literals not fitting column types
+        let bytes = encode(vec![call(
+            1,
+            vec![field(0, None), lit(LiteralType::String("abc".to_string()))],
+        )]);
+        assert!(matches!(
+            decode_extended_expression(&bytes, test_schema()),
+            Err(DataFusionError::Plan(_))
+        ));
+    }
+}
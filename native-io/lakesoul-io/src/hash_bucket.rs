@@ -0,0 +1,263 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hash buckets of primary keys, the same as Spark's `Murmur3Hash` with seed 42
+//! followed by `pmod` that LakeSoul uses in Spark and Flink.
+
+use arrow::array::{
+    as_boolean_array, as_primitive_array, as_string_array, Array, ArrayRef, BinaryArray, Decimal128Array,
+    LargeBinaryArray, LargeStringArray,
+};
+use arrow::datatypes::{
+    Date32Type, Date64Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+};
+use arrow::record_batch::RecordBatch;
+use arrow_schema::{DataType, TimeUnit};
+use datafusion::error::{DataFusionError, Result};
+
+/// Seed of Spark's `hash` function and `HashPartitioning`
+pub const HASH_SEED: i32 = 42;
+
+/// Hash bucket of each row by its values of columns
+pub fn hash_bucket_ids(batch: &RecordBatch, columns: &[String], hash_bucket_num: usize) -> Result<Vec<usize>> {
+    if hash_bucket_num == 0 {
+        return Err(DataFusionError::Plan("hash bucket num should be positive".to_string()));
+    }
+    let arrays = columns
+        .iter()
+        .map(|name| Ok(batch.column(batch.schema().index_of(name)?).clone()))
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    let hashes = spark_murmur3_hash(&arrays, batch.num_rows())?;
+    Ok(hashes
+        .into_iter()
+        .map(|hash| hash.rem_euclid(hash_bucket_num as i32) as usize)
+        .collect())
+}
+
+/// Spark's `Murmur3Hash` of rows of columns, nulls don't change hashes
+pub fn spark_murmur3_hash(columns: &[ArrayRef], num_rows: usize) -> Result<Vec<i32>> {
+    let mut hashes = vec![HASH_SEED; num_rows];
+    for column in columns {
+        hash_array(column.as_ref(), &mut hashes)?;
+    }
+    Ok(hashes)
+}
+
+fn hash_array(array: &dyn Array, hashes: &mut [i32]) -> Result<()> {
+    macro_rules! hash_values {
+        ($array:expr, $hash:expr) => {
+            for (i, hash) in hashes.iter_mut().enumerate() {
+                if $array.is_valid(i) {
+                    *hash = $hash($array.value(i), *hash);
+                }
+            }
+        };
+    }
+    match array.data_type() {
+        DataType::Boolean => hash_values!(as_boolean_array(array), |v: bool, seed| hash_int(v as i32, seed)),
+        DataType::Int8 => hash_values!(as_primitive_array::<Int8Type>(array), |v: i8, seed| hash_int(
+            v as i32, seed
+        )),
+        DataType::Int16 => hash_values!(as_primitive_array::<Int16Type>(array), |v: i16, seed| hash_int(
+            v as i32, seed
+        )),
+        DataType::Int32 => hash_values!(as_primitive_array::<Int32Type>(array), hash_int),
+        DataType::Int64 => hash_values!(as_primitive_array::<Int64Type>(array), hash_long),
+        DataType::Date32 => hash_values!(as_primitive_array::<Date32Type>(array), hash_int),
+        // dates are days in Spark
+        DataType::Date64 => hash_values!(as_primitive_array::<Date64Type>(array), |v: i64, seed| hash_int(
+            v.div_euclid(86_400_000) as i32,
+            seed
+        )),
+        // timestamps are microseconds in Spark, overflowing ones wrap around as Spark
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            hash_values!(as_primitive_array::<TimestampSecondType>(array), |v: i64, seed| {
+                hash_long(v.wrapping_mul(1_000_000), seed)
+            })
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            hash_values!(as_primitive_array::<TimestampMillisecondType>(array), |v: i64, seed| {
+                hash_long(v.wrapping_mul(1_000), seed)
+            })
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            hash_values!(as_primitive_array::<TimestampMicrosecondType>(array), hash_long)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            hash_values!(as_primitive_array::<TimestampNanosecondType>(array), |v: i64, seed| {
+                hash_long(v.div_euclid(1_000), seed)
+            })
+        }
+        DataType::Float32 => hash_values!(as_primitive_array::<Float32Type>(array), |v: f32, seed| {
+            // -0.0 is normalized and NaNs are canonical as Java's floatToIntBits
+            let bits = if v == 0.0 {
+                0
+            } else if v.is_nan() {
+                0x7fc00000
+            } else {
+                v.to_bits() as i32
+            };
+            hash_int(bits, seed)
+        }),
+        DataType::Float64 => hash_values!(as_primitive_array::<Float64Type>(array), |v: f64, seed| {
+            let bits = if v == 0.0 {
+                0
+            } else if v.is_nan() {
+                0x7ff8000000000000
+            } else {
+                v.to_bits() as i64
+            };
+            hash_long(bits, seed)
+        }),
+        DataType::Utf8 => hash_values!(as_string_array(array), |v: &str, seed| hash_bytes(v.as_bytes(), seed)),
+        DataType::LargeUtf8 => hash_values!(
+            array.as_any().downcast_ref::<LargeStringArray>().unwrap(),
+            |v: &str, seed| hash_bytes(v.as_bytes(), seed)
+        ),
+        DataType::Binary => hash_values!(array.as_any().downcast_ref::<BinaryArray>().unwrap(), hash_bytes),
+        DataType::LargeBinary => {
+            hash_values!(array.as_any().downcast_ref::<LargeBinaryArray>().unwrap(), hash_bytes)
+        }
+        // unscaled values of decimals fitting in longs, or bytes of BigInteger otherwise
+        DataType::Decimal128(precision, _) if *precision <= 18 => hash_values!(
+            array.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            |v: i128, seed| hash_long(v as i64, seed)
+        ),
+        DataType::Decimal128(_, _) => hash_values!(
+            array.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            |v: i128, seed| hash_bytes(&big_integer_bytes(v), seed)
+        ),
+        data_type => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "hash bucket of type {:?}",
+                data_type
+            )))
+        }
+    }
+    Ok(())
+}
+
+// Java's BigInteger.toByteArray, the minimal big-endian two's complement
+fn big_integer_bytes(v: i128) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+const C1: u32 = 0xcc9e2d51;
+const C2: u32 = 0x1b873593;
+
+fn mix_k1(k1: u32) -> u32 {
+    k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2)
+}
+
+fn mix_h1(h1: u32, k1: u32) -> u32 {
+    (h1 ^ k1).rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64)
+}
+
+fn fmix(mut h1: u32, length: u32) -> i32 {
+    h1 ^= length;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85ebca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2ae35);
+    h1 ^= h1 >> 16;
+    h1 as i32
+}
+
+fn hash_int(input: i32, seed: i32) -> i32 {
+    fmix(mix_h1(seed as u32, mix_k1(input as u32)), 4)
+}
+
+fn hash_long(input: i64, seed: i32) -> i32 {
+    let h1 = mix_h1(seed as u32, mix_k1(input as u32));
+    let h1 = mix_h1(h1, mix_k1((input >> 32) as u32));
+    fmix(h1, 8)
+}
+
+// Spark's hashUnsafeBytes, which mixes trailing bytes one by one as signed ints
+fn hash_bytes(bytes: &[u8], seed: i32) -> i32 {
+    let aligned = bytes.len() - bytes.len() % 4;
+    let mut h1 = seed as u32;
+    for chunk in bytes[..aligned].chunks_exact(4) {
+        h1 = mix_h1(h1, mix_k1(u32::from_le_bytes(chunk.try_into().unwrap())));
+    }
+    for byte in &bytes[aligned..] {
+        h1 = mix_h1(h1, mix_k1(*byte as i8 as i32 as u32));
+    }
+    fmix(h1, bytes.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray, TimestampMicrosecondArray, TimestampSecondArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_spark_murmur3_hash() -> Result<()> {
+        // values of Spark SQL `hash(1)`, `hash('Spark')` and `hash(1, 'Spark')`
+        assert_eq!(hash_int(1, HASH_SEED), -559580957);
+        assert_eq!(hash_bytes(b"Spark", HASH_SEED), 228093765);
+        let columns = vec![
+            Arc::new(Int32Array::from(vec![Some(1), None])) as ArrayRef,
+            Arc::new(StringArray::from(vec![Some("Spark"), Some("Spark")])) as ArrayRef,
+        ];
+        let hashes = spark_murmur3_hash(&columns, 2)?;
+        assert_eq!(hashes[0], hash_bytes(b"Spark", -559580957));
+        assert_eq!(hashes[1], 228093765);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_bucket_ids() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef)])?;
+        let ids = hash_bucket_ids(&batch, &["id".to_string()], 4)?;
+        let expected = [1, 2, 3]
+            .iter()
+            .map(|v| hash_int(*v, HASH_SEED).rem_euclid(4) as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+        // -559580957 pmod 4
+        assert_eq!(ids[0], 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamp_overflow() -> Result<()> {
+        let seconds = Arc::new(TimestampSecondArray::from(vec![i64::MAX])) as ArrayRef;
+        let micros = Arc::new(TimestampMicrosecondArray::from(vec![i64::MAX.wrapping_mul(1_000_000)])) as ArrayRef;
+        assert_eq!(spark_murmur3_hash(&[seconds], 1)?, spark_murmur3_hash(&[micros], 1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_integer_bytes() {
+        assert_eq!(big_integer_bytes(0), vec![0x00]);
+        assert_eq!(big_integer_bytes(128), vec![0x00, 0x80]);
+        assert_eq!(big_integer_bytes(-1), vec![0xff]);
+        assert_eq!(big_integer_bytes(-129), vec![0xff, 0x7f]);
+    }
+}
@@ -14,18 +14,23 @@
  * limitations under the License.
  */
 
-use crate::filter::Parser as FilterParser;
+use crate::filter::{decode_extended_expression, parse_sql_expr, parse_sql_filter, Parser as FilterParser};
+use crate::lakesoul_writer::parse_compression;
+use crate::sorted_merge::merge_operator::{MergeOperator, MergeOperatorRegistry};
 use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::DFSchema;
 pub use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
-use datafusion::logical_expr::Expr;
+use datafusion::logical_expr::{Expr, ExprSchemable};
 use datafusion::prelude::{SessionConfig, SessionContext};
 use derivative::Derivative;
 use object_store::aws::AmazonS3Builder;
 use object_store::RetryConfig;
+use parquet::file::metadata::ParquetMetaData;
 use std::collections::HashMap;
 use std::sync::Arc;
-use arrow_schema::{Schema, SchemaRef};
+use arrow_schema::{DataType, Schema, SchemaRef};
 use url::Url;
 
 #[derive(Derivative)]
@@ -38,6 +43,109 @@ impl Default for IOSchema {
     }
 }
 
+/// Partition value of nulls in partition descs and paths
+pub const LAKESOUL_NULL_STRING: &str = "__L@KE$OUL_NULL__";
+/// Partition value of empty strings in partition descs and paths
+pub const LAKESOUL_EMPTY_STRING: &str = "__L@KE$OUL_EMPTY_STRING__";
+/// Partition desc of tables without range partitions
+pub const LAKESOUL_NON_PARTITION_DESC: &str = "-5";
+
+// characters of partition values escaped by `escapePathName` of Hive and Spark,
+// and `,` separating columns in partition descs
+fn should_escape(c: char) -> bool {
+    (c.is_ascii_control() && c != '\0') || "\"#%'*/:=?\\{[]^,".contains(c)
+}
+
+/// Escape a partition value to be used in paths and partition descs, like `a/b` to `a%2Fb`
+pub fn escape_partition_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if should_escape(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Unescape a partition value of paths and partition descs, the reverse of `escape_partition_value`
+pub fn unescape_partition_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(idx) = rest.find('%') {
+        unescaped.push_str(&rest[..idx]);
+        let hex = rest
+            .get(idx + 1..idx + 3)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(code) => {
+                unescaped.push(code as char);
+                rest = &rest[idx + 3..];
+            }
+            None => {
+                unescaped.push('%');
+                rest = &rest[idx + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// How the reader handles rows of the change kind column of CDC tables
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CdcReadMode {
+    /// merge rows of each primary key and drop keys whose newest row is a delete
+    #[default]
+    Merged,
+    /// emit every row with its change kind sorted by primary keys, for incremental consumers
+    Changelog,
+}
+
+/// Part of a file read by one reader, so that several readers could
+/// decode disjoint parts of a large file in parallel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSplit {
+    /// row groups whose midpoint lies in the byte range `[start, start + length)`,
+    /// same as input splits of Spark and Flink
+    ByteRange { start: i64, length: i64 },
+    /// row groups of the given indices
+    RowGroups(Vec<usize>),
+}
+
+impl FileSplit {
+    pub(crate) fn row_groups(&self, metadata: &ParquetMetaData) -> Result<Vec<usize>> {
+        match self {
+            FileSplit::ByteRange { start, length } => Ok(metadata
+                .row_groups()
+                .iter()
+                .enumerate()
+                .filter(|(_, row_group)| {
+                    let column = row_group.column(0);
+                    let offset = column
+                        .dictionary_page_offset()
+                        .filter(|offset| *offset > 0)
+                        .unwrap_or_else(|| column.data_page_offset());
+                    let midpoint = offset + row_group.compressed_size() / 2;
+                    midpoint >= *start && midpoint < start + length
+                })
+                .map(|(idx, _)| idx)
+                .collect()),
+            FileSplit::RowGroups(row_groups) => {
+                if let Some(idx) = row_groups.iter().find(|idx| **idx >= metadata.num_row_groups()) {
+                    return Err(DataFusionError::Internal(format!(
+                        "row group {} out of range, file has {} row groups",
+                        idx,
+                        metadata.num_row_groups()
+                    )));
+                }
+                Ok(row_groups.clone())
+            }
+        }
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Default, Clone)]
 pub struct LakeSoulIOConfig {
@@ -45,24 +153,94 @@ pub struct LakeSoulIOConfig {
     pub(crate) files: Vec<String>,
     // primary key column names
     pub(crate) primary_keys: Vec<String>,
+    // merge operator names of non primary key columns, default to UseLast
+    pub(crate) merge_operators: HashMap<String, String>,
+    // merge operators could be referred by name, including built-in ones
+    pub(crate) merge_operator_registry: MergeOperatorRegistry,
+    // change kind column of CDC tables, named by lakesoul_cdc_change_column table property
+    pub(crate) cdc_column: Option<String>,
+    pub(crate) cdc_read_mode: CdcReadMode,
     // selecting columns
     pub(crate) columns: Vec<String>,
+    // computed columns aliased by output names, output after selected columns or all columns if none selected
+    pub(crate) projections: Vec<Expr>,
     // auxiliary sorting columns
     pub(crate) aux_sort_cols: Vec<String>,
 
     // filtering predicates
     pub(crate) filters: Vec<Expr>,
+    // errors of invalid filters added without checking, reported when the reader is created
+    pub(crate) filter_errors: Vec<String>,
+    // max number of rows to read
+    pub(crate) limit: Option<usize>,
+    // only read rows of these primary keys, one column per primary key
+    pub(crate) lookup_keys: Option<RecordBatch>,
+    // number of hash buckets primary key tables are partitioned into
+    pub(crate) hash_bucket_num: Option<usize>,
+    // hash bucket of all files read
+    pub(crate) hash_bucket_id: Option<usize>,
     // read or write batch size
     #[derivative(Default(value = "8192"))]
     pub(crate) batch_size: usize,
     // write row group max row num
     #[derivative(Default(value = "250000"))]
     pub(crate) max_row_group_size: usize,
+    // roll to the next file once bytes of row groups written reach this size
+    pub(crate) max_file_size: Option<u64>,
+    // roll to the next file once this many rows are written
+    pub(crate) max_file_rows: Option<usize>,
+    // parquet compression codec of written files like `zstd` or `gzip`, snappy if not set,
+    // levels are not supported by parquet 31
+    pub(crate) compression: Option<String>,
+    // compression codecs of columns overriding the codec of files
+    pub(crate) column_compressions: HashMap<String, String>,
+    // errors of invalid codecs set by object store options, reported when the writer is created
+    pub(crate) option_errors: Vec<String>,
+    // dictionary encoding of written files and columns, enabled if not set
+    pub(crate) dictionary_enabled: Option<bool>,
+    pub(crate) column_dictionary_enabled: HashMap<String, bool>,
+    // encodings of values not dictionary encoded like `delta_binary_packed`, plain if not set
+    pub(crate) encoding: Option<String>,
+    pub(crate) column_encodings: HashMap<String, String>,
+    // statistics level `none`, `chunk` or `page` of written files and columns, page if not set
+    pub(crate) statistics_enabled: Option<String>,
+    pub(crate) column_statistics_enabled: HashMap<String, String>,
+    // limits of bytes of data pages and dictionary pages, 1MB if not set
+    pub(crate) data_page_size: Option<usize>,
+    pub(crate) dictionary_page_size: Option<usize>,
+    // created by of written files, parquet-rs version if not set
+    pub(crate) created_by: Option<String>,
     #[derivative(Default(value = "2"))]
     pub(crate) prefetch_size: usize,
+    // whether batches of tables without primary keys are read in the order of files,
+    // otherwise files are read concurrently and batches are emitted as soon as decoded
+    #[derivative(Default(value = "true"))]
+    pub(crate) keep_file_order: bool,
+    // skip row groups by bloom filters of parquet files for equality predicates
+    #[derivative(Default(value = "true"))]
+    pub(crate) bloom_filter_pruning: bool,
+    // skip pages by column and offset indexes of parquet files
+    #[derivative(Default(value = "true"))]
+    pub(crate) page_index_pruning: bool,
 
     // arrow schema
     pub(crate) schema: IOSchema,
+    // values of columns missing from older files, parsed by column type of schema
+    pub(crate) default_column_values: HashMap<String, String>,
+    // columns written in each file by partial upsert, other columns of these files
+    // are not merged so older values are kept. Files not listed contain all their columns.
+    pub(crate) file_exist_cols: HashMap<String, Vec<String>>,
+    // only read part of these files
+    pub(crate) file_splits: HashMap<String, FileSplit>,
+    // values of range partition columns of the files, parsed by column type of schema
+    pub(crate) partition_values: HashMap<String, String>,
+    // range partition columns of the table, which are not written in files
+    pub(crate) range_partitions: Vec<String>,
+    // directory files of partitions are written under
+    pub(crate) table_path: Option<String>,
+    // file names are like `{prefix}-{bucket id}.parquet`, unique among writers of a table
+    #[derivative(Default(value = "String::from(\"part\")"))]
+    pub(crate) file_name_prefix: String,
 
     // object store related configs
     pub(crate) object_store_options: HashMap<String, String>,
@@ -72,6 +250,27 @@ pub struct LakeSoulIOConfig {
     pub(crate) thread_num: usize,
 }
 
+impl LakeSoulIOConfig {
+    /// Partition desc of files, like `date=2023-01-01,region=cn` in the order of range partitions,
+    /// with values escaped as in paths of partitions
+    pub fn partition_desc(&self) -> String {
+        if self.range_partitions.is_empty() {
+            return LAKESOUL_NON_PARTITION_DESC.to_string();
+        }
+        self.range_partitions
+            .iter()
+            .map(|col| {
+                let value = self
+                    .partition_values
+                    .get(col)
+                    .map_or(LAKESOUL_NULL_STRING, String::as_str);
+                format!("{}={}", col, escape_partition_value(value))
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Clone)]
 pub struct LakeSoulIOConfigBuilder {
@@ -105,16 +304,81 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    pub fn with_merge_op(mut self, col: String, merge_op: String) -> Self {
+        self.config.merge_operators.insert(col, merge_op);
+        self
+    }
+
+    pub fn with_custom_merge_operator(mut self, name: String, merge_operator: Arc<dyn MergeOperator>) -> Self {
+        self.config.merge_operator_registry.register(name, merge_operator);
+        self
+    }
+
+    pub fn with_cdc_column(mut self, cdc_column: String) -> Self {
+        self.config.cdc_column = Some(cdc_column);
+        self
+    }
+
+    pub fn with_cdc_read_mode(mut self, cdc_read_mode: CdcReadMode) -> Self {
+        self.config.cdc_read_mode = cdc_read_mode;
+        self
+    }
+
+    /// Select a column, or a value nested in it by a path like `a.b[1]['k']`
     pub fn with_column(mut self, col: String) -> Self {
         self.config.columns.push(String::from(&col));
         self
     }
 
+    /// Add a column computed from other columns of each batch, named by alias
+    pub fn with_projection(mut self, expr: Expr, alias: String) -> Self {
+        self.config.projections.push(expr.alias(alias));
+        self
+    }
+
+    /// Add a column computed by a SQL expression like `coalesce(a, 0) + 1`,
+    /// schema should be set before
+    pub fn with_sql_projection(mut self, sql: &str, alias: String) -> Result<Self> {
+        self.add_sql_projection(sql, alias)?;
+        Ok(self)
+    }
+
+    /// Same as `with_sql_projection`, but the builder is kept unchanged if the expression is invalid
+    pub fn add_sql_projection(&mut self, sql: &str, alias: String) -> Result<()> {
+        let expr = parse_sql_expr(sql, self.config.schema.0.clone())
+            .map_err(|e| DataFusionError::Plan(format!("invalid sql projection: {}", e)))?;
+        self.config.projections.push(expr.alias(alias));
+        Ok(())
+    }
+
     pub fn with_aux_sort_column(mut self, col: String) -> Self {
         self.config.aux_sort_cols.push(String::from(&col));
         self
     }
 
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    pub fn with_hash_bucket_num(mut self, hash_bucket_num: usize) -> Self {
+        self.config.hash_bucket_num = Some(hash_bucket_num);
+        self
+    }
+
+    /// Declare that all files read are of the hash bucket
+    pub fn with_hash_bucket_id(mut self, hash_bucket_id: usize) -> Self {
+        self.config.hash_bucket_id = Some(hash_bucket_id);
+        self
+    }
+
+    /// Only read rows of primary keys in keys, which has a column named by each primary key.
+    /// Rows are found by binary search in files sorted by primary keys and merged as a full scan.
+    pub fn with_lookup_keys(mut self, keys: RecordBatch) -> Self {
+        self.config.lookup_keys = Some(keys);
+        self
+    }
+
     pub fn with_batch_size(mut self, batch_size: usize) -> Self {
         self.config.batch_size = batch_size;
         self
@@ -125,11 +389,116 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.config.max_file_size = Some(max_file_size);
+        self
+    }
+
+    pub fn with_max_file_rows(mut self, max_file_rows: usize) -> Self {
+        self.config.max_file_rows = Some(max_file_rows);
+        self
+    }
+
+    /// Compression codec of written files, one of `uncompressed`, `snappy`, `gzip`, `lzo`,
+    /// `brotli`, `lz4`, `lz4_raw` and `zstd`. Levels like `zstd(19)` are validated but rejected
+    /// as not implemented, since the parquet writer always uses the default level of codecs
+    pub fn with_compression(mut self, codec: String) -> Result<Self> {
+        self.set_compression(codec)?;
+        Ok(self)
+    }
+
+    /// Same as `with_compression`, but the builder is kept unchanged if the codec is invalid
+    pub fn set_compression(&mut self, codec: String) -> Result<()> {
+        parse_compression(&codec)?;
+        self.config.compression = Some(codec);
+        Ok(())
+    }
+
+    /// Compression codec of a column, like `a.b` for nested columns. The column is checked
+    /// against schema when the writer is created
+    pub fn with_column_compression(mut self, column: String, codec: String) -> Result<Self> {
+        self.set_column_compression(column, codec)?;
+        Ok(self)
+    }
+
+    /// Same as `with_column_compression`, but the builder is kept unchanged if the codec is invalid
+    pub fn set_column_compression(&mut self, column: String, codec: String) -> Result<()> {
+        parse_compression(&codec)?;
+        self.config.column_compressions.insert(column, codec);
+        Ok(())
+    }
+
+    pub fn with_dictionary_enabled(mut self, enabled: bool) -> Self {
+        self.config.dictionary_enabled = Some(enabled);
+        self
+    }
+
+    pub fn with_column_dictionary_enabled(mut self, column: String, enabled: bool) -> Self {
+        self.config.column_dictionary_enabled.insert(column, enabled);
+        self
+    }
+
+    /// Encoding of values not dictionary encoded, one of `plain`, `rle`, `delta_binary_packed`,
+    /// `delta_length_byte_array`, `delta_byte_array` and `byte_stream_split`.
+    /// It only applies to columns of physical types supporting it, e.g. integers for `delta_binary_packed`,
+    /// while encodings of columns should be supported by their types.
+    pub fn with_encoding(mut self, encoding: String) -> Self {
+        self.config.encoding = Some(encoding);
+        self
+    }
+
+    pub fn with_column_encoding(mut self, column: String, encoding: String) -> Self {
+        self.config.column_encodings.insert(column, encoding);
+        self
+    }
+
+    /// Statistics written in files, `none`, `chunk` for column chunks or `page` for
+    /// column chunks and pages
+    pub fn with_statistics_enabled(mut self, level: String) -> Self {
+        self.config.statistics_enabled = Some(level);
+        self
+    }
+
+    pub fn with_column_statistics_enabled(mut self, column: String, level: String) -> Self {
+        self.config.column_statistics_enabled.insert(column, level);
+        self
+    }
+
+    pub fn with_data_page_size(mut self, data_page_size: usize) -> Self {
+        self.config.data_page_size = Some(data_page_size);
+        self
+    }
+
+    pub fn with_dictionary_page_size(mut self, dictionary_page_size: usize) -> Self {
+        self.config.dictionary_page_size = Some(dictionary_page_size);
+        self
+    }
+
+    pub fn with_created_by(mut self, created_by: String) -> Self {
+        self.config.created_by = Some(created_by);
+        self
+    }
+
     pub fn with_prefetch_size(mut self, prefetch_size: usize) -> Self {
         self.config.prefetch_size = prefetch_size;
         self
     }
 
+    pub fn with_keep_file_order(mut self, keep_file_order: bool) -> Self {
+        self.config.keep_file_order = keep_file_order;
+        self
+    }
+
+    pub fn with_bloom_filter_pruning(mut self, bloom_filter_pruning: bool) -> Self {
+        self.config.bloom_filter_pruning = bloom_filter_pruning;
+        self
+    }
+
+    pub fn with_page_index_pruning(mut self, page_index_pruning: bool) -> Self {
+        self.config.page_index_pruning = page_index_pruning;
+        self
+    }
+
     pub fn with_columns(mut self, cols: Vec<String>) -> Self {
         self.config.columns = cols;
         self
@@ -140,19 +509,166 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
-    pub fn with_filter_str(mut self, filter_str: String) -> Self {
-        let expr = FilterParser::parse(filter_str, self.config.schema.0.clone());
-        self.config.filters.push(expr);
+    pub fn with_default_column_value(mut self, col: String, value: String) -> Self {
+        self.config.default_column_values.insert(col, value);
+        self
+    }
+
+    pub fn with_file_split(mut self, file: String, split: FileSplit) -> Self {
+        self.config.file_splits.insert(file, split);
+        self
+    }
+
+    pub fn with_partition_value(mut self, col: String, value: String) -> Self {
+        self.config.partition_values.insert(col, value);
+        self
+    }
+
+    pub fn with_range_partition(mut self, col: String) -> Self {
+        self.config.range_partitions.push(col);
+        self
+    }
+
+    pub fn with_range_partitions(mut self, cols: Vec<String>) -> Self {
+        self.config.range_partitions = cols;
         self
     }
 
+    pub fn with_table_path(mut self, table_path: String) -> Self {
+        self.config.table_path = Some(table_path);
+        self
+    }
+
+    pub fn with_file_name_prefix(mut self, prefix: String) -> Self {
+        self.config.file_name_prefix = prefix;
+        self
+    }
+
+    /// Set all partition values from a partition desc like `date=2023-01-01,region=cn`,
+    /// whose values are escaped like `a%2Fb` for `a/b`
+    pub fn with_partition_desc(mut self, partition_desc: &str) -> Self {
+        for part in partition_desc.split(',').filter(|p| !p.is_empty()) {
+            if let Some((col, value)) = part.split_once('=') {
+                self.config
+                    .partition_values
+                    .insert(col.to_string(), unescape_partition_value(value));
+            }
+        }
+        self
+    }
+
+    pub fn with_file_exist_cols(mut self, file: String, cols: Vec<String>) -> Self {
+        self.config.file_exist_cols.insert(file, cols);
+        self
+    }
+
+    /// Parse a filter string by column types of schema, so schema should be set before
+    pub fn with_filter_str(mut self, filter_str: String) -> Result<Self> {
+        self.add_filter_str(filter_str)?;
+        Ok(self)
+    }
+
+    /// Same as `with_filter_str`, but the builder is kept unchanged if the filter is invalid
+    pub fn add_filter_str(&mut self, filter_str: String) -> Result<()> {
+        let expr = FilterParser::parse(filter_str, self.config.schema.0.clone())
+            .map_err(|e| DataFusionError::Plan(format!("invalid filter: {}", e)))?;
+        self.config.filters.push(expr);
+        Ok(())
+    }
+
+    /// Same as `add_filter_str`, but an invalid filter is kept as an error reported when
+    /// the reader is created, so that it's never skipped silently
+    pub fn add_filter_str_deferred(&mut self, filter_str: String) {
+        if let Err(e) = self.add_filter_str(filter_str) {
+            self.config.filter_errors.push(e.to_string());
+        }
+    }
+
+    /// Parse a SQL boolean expression like `region = 'cn' AND ts > TIMESTAMP '2023-01-01'`
+    /// by column types of schema, so schema should be set before
+    pub fn with_sql_filter(mut self, sql: &str) -> Result<Self> {
+        self.add_sql_filter(sql)?;
+        Ok(self)
+    }
+
+    /// Same as `with_sql_filter`, but the builder is kept unchanged if the filter is invalid
+    pub fn add_sql_filter(&mut self, sql: &str) -> Result<()> {
+        let expr = parse_sql_filter(sql, self.config.schema.0.clone())
+            .map_err(|e| DataFusionError::Plan(format!("invalid sql filter: {}", e)))?;
+        self.config.filters.push(expr);
+        Ok(())
+    }
+
+    /// Add predicates decoded from a serialized Substrait `ExtendedExpression`,
+    /// field references are resolved against schema so schema should be set before
+    pub fn with_substrait_filter(mut self, extended_expression: &[u8]) -> Result<Self> {
+        self.add_substrait_filter(extended_expression)?;
+        Ok(self)
+    }
+
+    /// Same as `with_substrait_filter`, but the builder is kept unchanged if any predicate is invalid
+    pub fn add_substrait_filter(&mut self, extended_expression: &[u8]) -> Result<()> {
+        let df_schema = DFSchema::try_from(self.config.schema.0.as_ref().clone())?;
+        let mut filters = vec![];
+        for decoded in decode_extended_expression(extended_expression, self.config.schema.0.clone())? {
+            let data_type = decoded.expr.get_type(&df_schema)?;
+            if data_type != DataType::Boolean {
+                return Err(DataFusionError::Plan(format!(
+                    "substrait filter {:?} is of type {:?} instead of boolean",
+                    decoded.expr, data_type
+                )));
+            }
+            filters.push(decoded.expr);
+        }
+        self.config.filters.extend(filters);
+        Ok(())
+    }
+
+    /// Select columns referenced by a serialized Substrait `ExtendedExpression`,
+    /// each expression must be a field reference
+    pub fn with_substrait_projection(mut self, extended_expression: &[u8]) -> Result<Self> {
+        self.add_substrait_projection(extended_expression)?;
+        Ok(self)
+    }
+
+    /// Same as `with_substrait_projection`, but the builder is kept unchanged if any expression is invalid
+    pub fn add_substrait_projection(&mut self, extended_expression: &[u8]) -> Result<()> {
+        let columns = decode_extended_expression(extended_expression, self.config.schema.0.clone())?
+            .into_iter()
+            .map(|decoded| {
+                decoded.column.ok_or_else(|| {
+                    DataFusionError::NotImplemented(format!(
+                        "substrait projection {:?} is not a field reference",
+                        decoded.expr
+                    ))
+                })
+            })
+            .collect::<Result<Vec<String>>>()?;
+        self.config.columns.extend(columns);
+        Ok(())
+    }
+
     pub fn with_filters(mut self, filters: Vec<Expr>) -> Self {
         self.config.filters = filters;
         self
     }
 
+    /// Options `parquet.compression` and `parquet.compression#{column}` set compression codecs
+    /// of written files and their columns, as hadoop parquet configs. Codecs are validated
+    /// as `set_compression`, but as options can't fail, an invalid codec is kept as an error
+    /// reported when the writer is created
     pub fn with_object_store_option(mut self, key: String, value: String) -> Self {
-        self.config.object_store_options.insert(key, value);
+        let result = if key == "parquet.compression" {
+            self.set_compression(value)
+        } else if let Some(column) = key.strip_prefix("parquet.compression#") {
+            self.set_column_compression(column.to_string(), value)
+        } else {
+            self.config.object_store_options.insert(key, value);
+            Ok(())
+        };
+        if let Err(e) = result {
+            self.config.option_errors.push(e.to_string());
+        }
         self
     }
 
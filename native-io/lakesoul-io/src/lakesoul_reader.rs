@@ -25,70 +25,945 @@ pub use datafusion::error::{DataFusionError, Result};
 use datafusion::prelude::SessionContext;
 
 use core::pin::Pin;
-use arrow_schema::SchemaRef;
-use datafusion::physical_plan::RecordBatchStream;
-use futures::StreamExt;
+use arrow::array::{as_boolean_array, build_compare, Array, ArrayRef};
+use arrow::compute::{cast, concat_batches, filter_record_batch, lexsort_to_indices, take, SortColumn, SortOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use bytes::Bytes;
+use datafusion::common::DFSchema;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::logical_expr::utils::expr_to_columns;
+use datafusion::logical_expr::{col, Expr};
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_expr::execution_props::ExecutionProps;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{create_physical_expr, PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::limit::GlobalLimitExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{ExecutionPlan, RecordBatchStream, SendableRecordBatchStream};
+use datafusion::scalar::ScalarValue;
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::arrow_reader::{ArrowReaderOptions, RowSelection, RowSelector};
+use parquet::arrow::async_reader::{fetch_parquet_metadata, AsyncFileReader, ParquetRecordBatchStreamBuilder};
+use parquet::arrow::{parquet_to_arrow_schema, ProjectionMask};
+use parquet::errors::ParquetError;
+use parquet::file::metadata::ParquetMetaData;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
 
 use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
-use crate::lakesoul_io_config::{create_session_context, LakeSoulIOConfig};
+use crate::column_path::{prune_field, ColumnPath};
+use crate::lakesoul_io_config::{
+    create_session_context, FileSplit, LakeSoulIOConfig, LAKESOUL_EMPTY_STRING, LAKESOUL_NULL_STRING,
+};
+use crate::lakesoul_writer::ReceiverStreamExec;
+use crate::pruning::{column_predicates, FilePruner, PruningMetrics};
+use crate::sorted_merge::SortedStreamMerger;
+use crate::transform::{check_schema_compatible, uniform_record_batch};
 
 pub struct LakeSoulReader {
     sess_ctx: SessionContext,
     config: LakeSoulIOConfig,
     stream: Box<MaybeUninit<Pin<Box<dyn RecordBatchStream + Send>>>>,
     pub(crate) schema: Option<SchemaRef>,
+    metrics: Arc<PruningMetrics>,
+    output_ordering: Option<Vec<PhysicalSortExpr>>,
 }
 
 impl LakeSoulReader {
     pub fn new(mut config: LakeSoulIOConfig) -> Result<Self> {
+        if !config.filter_errors.is_empty() {
+            return Err(DataFusionError::Plan(config.filter_errors.join("; ")));
+        }
         let sess_ctx = create_session_context(&mut config)?;
         Ok(LakeSoulReader {
             sess_ctx,
             config,
             stream: Box::new_uninit(),
             schema: None,
+            metrics: Arc::new(PruningMetrics::default()),
+            output_ordering: None,
         })
     }
 
+    /// Hash bucket of all files read, if given in config
+    pub fn hash_bucket_id(&self) -> Option<usize> {
+        self.config.hash_bucket_id
+    }
+
+    /// Order of rows read once started, rows of tables with primary keys are sorted by them
+    pub fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        self.output_ordering.clone()
+    }
+
+    /// Numbers of row groups and pages skipped by filters so far
+    pub fn pruning_metrics(&self) -> Arc<PruningMetrics> {
+        self.metrics.clone()
+    }
+
     pub async fn start(&mut self) -> Result<()> {
-        let mut df = self
-            .sess_ctx
-            .read_parquet(self.config.files[0].as_str(), Default::default())
-            .await?;
-        if !self.config.columns.is_empty() {
-            let cols: Vec<_> = self.config.columns.iter().map(String::as_str).collect();
-            df = df.select_columns(&cols)?;
+        if self.config.files.is_empty() {
+            return Err(DataFusionError::Internal("no file provided for reader".to_string()));
+        }
+        if let (Some(id), Some(num)) = (self.config.hash_bucket_id, self.config.hash_bucket_num) {
+            if id >= num {
+                return Err(DataFusionError::Plan(format!(
+                    "hash bucket id {} out of {} buckets",
+                    id, num
+                )));
+            }
+        }
+        // files opened before reading them, so that their metadata is fetched only once
+        let mut opened_files = HashMap::new();
+        let target_schema = self.target_schema(&mut opened_files).await?;
+        let read_schema = self.read_schema(&target_schema)?;
+        let partition_values = self.partition_values(&target_schema)?;
+        let mut default_values = self.default_values(&read_schema)?;
+        // partition columns are not stored in files and filled with constant values
+        default_values.extend(partition_values.clone());
+
+        // Predicates only referencing partition columns have the same result for all rows,
+        // so they are evaluated once here instead of for every row.
+        let (partition_filters, filters) = self.split_partition_filters(&partition_values)?;
+        if !eval_partition_filters(&partition_filters, &partition_values)? {
+            let plan = Arc::new(EmptyExec::new(false, read_schema.clone()));
+            self.stream = Box::new(MaybeUninit::new(self.project(plan, &read_schema)?));
+            return Ok(());
         }
-        df = self.config.filters.iter().try_fold(df, |df, f| df.filter(f.clone()))?;
-        self.schema = Some(df.schema().clone().into());
 
-        self.stream = Box::new(MaybeUninit::new(df.execute_stream().await?));
+        // All versions of a primary key have the same key values, so skipping rows
+        // by filters of primary key columns doesn't change results of merging.
+        let lookup_keys = self.lookup_keys(&target_schema)?;
+        let mut pruning_filters = vec![];
+        if let Some(keys) = &lookup_keys {
+            for (pk, column) in self.config.primary_keys.iter().zip(keys.columns()) {
+                let values = (0..column.len())
+                    .map(|i| ScalarValue::try_from_array(column, i).map(Expr::Literal))
+                    .collect::<Result<Vec<_>>>()?;
+                pruning_filters.push(col(pk.as_str()).in_list(values, false));
+            }
+        }
+        for filter in &filters {
+            let mut filter_cols = HashSet::new();
+            expr_to_columns(filter, &mut filter_cols)?;
+            if self.config.primary_keys.is_empty()
+                || filter_cols.iter().all(|c| self.config.primary_keys.contains(&c.name))
+            {
+                pruning_filters.push(filter.clone());
+            }
+        }
+        let file_reader = Arc::new(FileReader {
+            sess_ctx: self.sess_ctx.clone(),
+            config: self.config.clone(),
+            read_schema: read_schema.clone(),
+            default_values,
+            opened_files,
+            pruning_filters,
+            metrics: self.metrics.clone(),
+            lookup_keys,
+        });
+        let plan: Arc<dyn ExecutionPlan> = if !self.config.primary_keys.is_empty() {
+            // Read all files sorted by primary keys and merge them into one row per key.
+            // Filters must be applied after merging, otherwise an older row could
+            // reappear when its newer version is filtered out.
+            let mut streams = Vec::with_capacity(self.config.files.len());
+            let mut existing_columns = Vec::with_capacity(self.config.files.len());
+            for file in &self.config.files {
+                let (stream, file_columns) = file_reader.read(file, &[]).await?;
+                streams.push(stream);
+                existing_columns.push(file_columns);
+            }
+            let merger = SortedStreamMerger::try_new(streams, existing_columns, read_schema.clone(), &self.config)?;
+            let (rx, join_handle) = merger.start();
+            // merged rows are sorted by primary keys as all files are
+            let ordering = self
+                .config
+                .primary_keys
+                .iter()
+                .map(|pk| {
+                    Ok(PhysicalSortExpr {
+                        expr: Arc::new(Column::new_with_schema(pk, &read_schema)?),
+                        options: SortOptions::default(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let plan: Arc<dyn ExecutionPlan> =
+                Arc::new(ReceiverStreamExec::new(rx, join_handle, read_schema.clone()).with_output_ordering(ordering));
+            match filters.into_iter().reduce(|a, b| a.and(b)) {
+                Some(filter) => {
+                    let df_schema = DFSchema::try_from(read_schema.as_ref().clone())?;
+                    let predicate = create_physical_expr(&filter, &df_schema, &read_schema, &ExecutionProps::new())?;
+                    Arc::new(FilterExec::try_new(predicate, plan)?)
+                }
+                None => plan,
+            }
+        } else {
+            // Rows of append-only tables are independent, so files are read
+            // concurrently and filtered separately.
+            let (rx, join_handle) = read_files_concurrently(file_reader, filters);
+            Arc::new(ReceiverStreamExec::new(rx, join_handle, read_schema.clone()))
+        };
+        // Stop reading once enough rows are produced after filtering and merging,
+        // files are no longer fetched when the limit stream drops its input.
+        let plan: Arc<dyn ExecutionPlan> = match self.config.limit {
+            Some(limit) => Arc::new(GlobalLimitExec::new(plan, 0, Some(limit))),
+            None => plan,
+        };
+        self.stream = Box::new(MaybeUninit::new(self.project(plan, &read_schema)?));
 
         Ok(())
     }
 
+    // project to selected columns followed by computed columns and execute the plan,
+    // nested column paths are output as columns named by the path
+    fn project(
+        &mut self,
+        mut plan: Arc<dyn ExecutionPlan>,
+        read_schema: &SchemaRef,
+    ) -> Result<SendableRecordBatchStream> {
+        let mut ordering = plan.output_ordering().map(|ordering| ordering.to_vec());
+        if !self.config.columns.is_empty() || !self.config.projections.is_empty() {
+            let df_schema = Arc::new(DFSchema::try_from(read_schema.as_ref().clone())?);
+            let props = ExecutionProps::new();
+            // all columns are selected if only computed columns are given
+            let mut proj_expr = if self.config.columns.is_empty() {
+                read_schema
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, f)| {
+                        let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(f.name(), idx));
+                        (expr, f.name().clone())
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                self.config
+                    .columns
+                    .iter()
+                    .map(|c| {
+                        let (_, expr, _) = ColumnPath::resolve(c, read_schema)?;
+                        create_physical_expr(&expr, &df_schema, read_schema, &props).map(|e| (e, c.clone()))
+                    })
+                    .collect::<Result<Vec<(Arc<dyn PhysicalExpr>, String)>>>()?
+            };
+            // types of computed columns are coerced as DataFusion does in planning
+            let simplifier = ExprSimplifier::new(SimplifyContext::new(&props).with_schema(df_schema.clone()));
+            for projection in &self.config.projections {
+                let (expr, name) = match projection {
+                    Expr::Alias(expr, name) => (expr.as_ref().clone(), name.clone()),
+                    expr => (expr.clone(), expr.display_name()?),
+                };
+                let expr = simplifier.coerce(expr, df_schema.clone())?;
+                proj_expr.push((create_physical_expr(&expr, &df_schema, read_schema, &props)?, name));
+            }
+            // ProjectionExec passes through ordering of its input, which refers to columns of read schema
+            ordering = ordering.map(|ordering| project_ordering(&ordering, &proj_expr));
+            plan = Arc::new(ProjectionExec::try_new(proj_expr, plan)?);
+        }
+        self.output_ordering = ordering.filter(|ordering| !ordering.is_empty());
+        self.schema = Some(self.schema_with_metadata(plan.schema()));
+        plan.execute(0, self.sess_ctx.task_ctx())
+    }
+
+    // Hash bucket and sort columns are kept in metadata of schema for readers through FFI
+    fn schema_with_metadata(&self, schema: SchemaRef) -> SchemaRef {
+        let mut metadata = schema.metadata().clone();
+        if let Some(id) = self.config.hash_bucket_id {
+            metadata.insert("lakesoul.hash_bucket_id".to_string(), id.to_string());
+        }
+        if let Some(num) = self.config.hash_bucket_num {
+            metadata.insert("lakesoul.hash_bucket_num".to_string(), num.to_string());
+        }
+        if let Some(ordering) = &self.output_ordering {
+            // rows are also sorted by a prefix of sort columns
+            let sort_columns = ordering
+                .iter()
+                .map_while(|sort| {
+                    sort.expr
+                        .as_any()
+                        .downcast_ref::<Column>()
+                        .map(|c| c.name().to_string())
+                })
+                .collect::<Vec<_>>();
+            if !sort_columns.is_empty() {
+                metadata.insert("lakesoul.sort_columns".to_string(), sort_columns.join(","));
+            }
+        }
+        if &metadata == schema.metadata() {
+            return schema;
+        }
+        Arc::new(Schema::new_with_metadata(schema.fields().clone(), metadata))
+    }
+
+    // Schema all files are adapted to: the table schema if given in config,
+    // otherwise schema of the first file with partition columns appended as strings
+    async fn target_schema(&self, opened_files: &mut HashMap<String, ObjectStoreFileReader>) -> Result<SchemaRef> {
+        if !self.config.schema.0.fields().is_empty() {
+            return Ok(self.config.schema.0.clone());
+        }
+        let file = &self.config.files[0];
+        let reader = ObjectStoreFileReader::open(&self.sess_ctx, file).await?;
+        let file_metadata = reader.metadata.file_metadata();
+        let file_schema = parquet_to_arrow_schema(file_metadata.schema_descr(), file_metadata.key_value_metadata())?;
+        opened_files.insert(file.clone(), reader);
+        let mut partition_cols = self
+            .config
+            .partition_values
+            .keys()
+            .filter(|name| file_schema.field_with_name(name).is_err())
+            .collect::<Vec<_>>();
+        partition_cols.sort();
+        let mut fields = file_schema.fields().clone();
+        fields.extend(
+            partition_cols
+                .into_iter()
+                .map(|name| Field::new(name, DataType::Utf8, true)),
+        );
+        Ok(Arc::new(Schema::new(fields)))
+    }
+
+    // values of range partition columns, typed by target schema
+    fn partition_values(&self, target_schema: &SchemaRef) -> Result<HashMap<String, ScalarValue>> {
+        self.config
+            .partition_values
+            .iter()
+            .map(|(name, value)| {
+                let data_type = target_schema.field_with_name(name)?.data_type();
+                let value = match value.as_str() {
+                    LAKESOUL_NULL_STRING => ScalarValue::try_from(data_type)?,
+                    LAKESOUL_EMPTY_STRING => ScalarValue::try_from_string(String::new(), data_type)?,
+                    value => ScalarValue::try_from_string(value.to_string(), data_type)?,
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    // split filters into those only referencing partition columns and the others
+    fn split_partition_filters(
+        &self,
+        partition_values: &HashMap<String, ScalarValue>,
+    ) -> Result<(Vec<Expr>, Vec<Expr>)> {
+        let mut partition_filters = vec![];
+        let mut filters = vec![];
+        for filter in &self.config.filters {
+            let mut filter_cols = HashSet::new();
+            expr_to_columns(filter, &mut filter_cols)?;
+            if !partition_values.is_empty() && filter_cols.iter().all(|c| partition_values.contains_key(&c.name)) {
+                partition_filters.push(filter.clone());
+            } else {
+                filters.push(filter.clone());
+            }
+        }
+        Ok((partition_filters, filters))
+    }
+
+    // Lookup keys with columns in the order of primary keys and types of target schema,
+    // sorted in the same order as files
+    fn lookup_keys(&self, target_schema: &Schema) -> Result<Option<RecordBatch>> {
+        let keys = match &self.config.lookup_keys {
+            Some(keys) => keys,
+            None => return Ok(None),
+        };
+        if self.config.primary_keys.is_empty() {
+            return Err(DataFusionError::Plan(
+                "lookup keys are only supported for tables with primary keys".to_string(),
+            ));
+        }
+        if keys.num_columns() != self.config.primary_keys.len() {
+            return Err(DataFusionError::Plan(format!(
+                "lookup keys should have columns of primary keys {:?}, got {:?}",
+                self.config.primary_keys,
+                keys.schema()
+            )));
+        }
+        let mut fields = Vec::with_capacity(keys.num_columns());
+        let mut columns = Vec::with_capacity(keys.num_columns());
+        for pk in &self.config.primary_keys {
+            let column = keys.column(keys.schema().index_of(pk)?);
+            if column.null_count() > 0 {
+                return Err(DataFusionError::Plan(format!("lookup keys of {} contain nulls", pk)));
+            }
+            let data_type = target_schema.field_with_name(pk)?.data_type();
+            columns.push(cast(column, data_type)?);
+            fields.push(Field::new(pk, data_type.clone(), false));
+        }
+        let sort_columns = columns
+            .iter()
+            .map(|values| SortColumn {
+                values: values.clone(),
+                options: None,
+            })
+            .collect::<Vec<_>>();
+        let indices = lexsort_to_indices(&sort_columns, None)?;
+        let columns = columns
+            .iter()
+            .map(|column| take(column.as_ref(), &indices, None))
+            .collect::<ArrowResult<Vec<_>>>()?;
+        Ok(Some(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?))
+    }
+
+    // Columns read from each file: roots of selected columns, primary keys, CDC column
+    // and columns referenced by filters or computed columns, in the order of target schema.
+    // Struct columns only selected by nested paths of struct fields are pruned to those fields.
+    // All columns are read if no column is selected.
+    fn read_schema(&self, target_schema: &SchemaRef) -> Result<SchemaRef> {
+        if self.config.columns.is_empty() {
+            return Ok(target_schema.clone());
+        }
+        let mut expr_cols = HashSet::new();
+        for expr in self.config.filters.iter().chain(&self.config.projections) {
+            expr_to_columns(expr, &mut expr_cols)?;
+        }
+        let selected = self
+            .config
+            .columns
+            .iter()
+            .map(|c| {
+                ColumnPath::resolve(c, target_schema).map(|(path, _, _)| (path.root.clone(), path.struct_prefix()))
+            })
+            .collect::<Result<Vec<(String, Vec<String>)>>>()?;
+        let fields = target_schema
+            .fields()
+            .iter()
+            .filter_map(|f| {
+                let name = f.name();
+                if self.config.primary_keys.contains(name)
+                    || self.config.cdc_column.as_ref() == Some(name)
+                    || expr_cols.iter().any(|c| &c.name == name)
+                {
+                    return Some(f.clone());
+                }
+                let paths = selected
+                    .iter()
+                    .filter(|(root, _)| root == name)
+                    .map(|(_, prefix)| prefix.as_slice())
+                    .collect::<Vec<_>>();
+                if paths.is_empty() {
+                    None
+                } else {
+                    Some(prune_field(f, &paths))
+                }
+            })
+            .collect::<Vec<Field>>();
+        Ok(Arc::new(Schema::new_with_metadata(
+            fields,
+            target_schema.metadata().clone(),
+        )))
+    }
+
+    // default values of columns missing from some files
+    fn default_values(&self, read_schema: &SchemaRef) -> Result<HashMap<String, ScalarValue>> {
+        read_schema
+            .fields()
+            .iter()
+            .filter_map(|f| {
+                self.config.default_column_values.get(f.name()).map(|value| {
+                    ScalarValue::try_from_string(value.clone(), f.data_type()).map(|v| (f.name().clone(), v))
+                })
+            })
+            .collect()
+    }
+
     pub async fn next_rb(&mut self) -> Option<ArrowResult<RecordBatch>> {
         unsafe { self.stream.assume_init_mut().next().await }
     }
 }
 
+// Ordering of projected rows by columns in the output of projection, which is the prefix
+// of sort columns until the first one not projected
+fn project_ordering(
+    ordering: &[PhysicalSortExpr],
+    proj_expr: &[(Arc<dyn PhysicalExpr>, String)],
+) -> Vec<PhysicalSortExpr> {
+    ordering
+        .iter()
+        .map_while(|sort| {
+            let column = sort.expr.as_any().downcast_ref::<Column>()?;
+            proj_expr.iter().enumerate().find_map(|(idx, (expr, name))| {
+                expr.as_any()
+                    .downcast_ref::<Column>()
+                    .filter(|c| c.index() == column.index())
+                    .map(|_| PhysicalSortExpr {
+                        expr: Arc::new(Column::new(name, idx)),
+                        options: sort.options,
+                    })
+            })
+        })
+        .collect()
+}
+
+// Evaluate predicates of partition columns on the single row of partition values
+fn eval_partition_filters(filters: &[Expr], partition_values: &HashMap<String, ScalarValue>) -> Result<bool> {
+    let filter = match filters.iter().cloned().reduce(|a, b| a.and(b)) {
+        Some(filter) => filter,
+        None => return Ok(true),
+    };
+    let columns = partition_values
+        .iter()
+        .map(|(name, value)| (name.clone(), value.to_array_of_size(1)))
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_from_iter(columns)?;
+    let schema = batch.schema();
+    let df_schema = DFSchema::try_from(schema.as_ref().clone())?;
+    let predicate = create_physical_expr(&filter, &df_schema, &schema, &ExecutionProps::new())?;
+    let result = predicate.evaluate(&batch)?.into_array(1);
+    let result = as_boolean_array(&result);
+    Ok(result.is_valid(0) && result.value(0))
+}
+
+// Reads files with the same read schema, shared by tasks reading files concurrently
+struct FileReader {
+    sess_ctx: SessionContext,
+    config: LakeSoulIOConfig,
+    read_schema: SchemaRef,
+    default_values: HashMap<String, ScalarValue>,
+    // files opened before reading, whose metadata has been fetched
+    opened_files: HashMap<String, ObjectStoreFileReader>,
+    // filters whose unmatched rows could be skipped before reading
+    pruning_filters: Vec<Expr>,
+    metrics: Arc<PruningMetrics>,
+    // sorted primary keys to look up
+    lookup_keys: Option<RecordBatch>,
+}
+
+impl FileReader {
+    // Read columns of read schema from one file, or the split of it given in config.
+    // Batches are adapted to read schema since older files may lack some columns
+    // or store them in narrower types, and filters are evaluated then.
+    // Also returns columns of read schema written in the file, which are all columns
+    // unless the file is given its existing columns by a partial upsert in config.
+    async fn read(&self, file: &str, filters: &[Expr]) -> Result<(SendableRecordBatchStream, Vec<String>)> {
+        let reader = match self.opened_files.get(file) {
+            Some(reader) => reader.clone(),
+            None => ObjectStoreFileReader::open(&self.sess_ctx, file).await?,
+        };
+        let split = self.config.file_splits.get(file);
+        let (stream, existing_columns) = self.read_leaves(file, reader, split).await?;
+        let predicate = match filters.iter().cloned().reduce(|a, b| a.and(b)) {
+            Some(filter) => {
+                let df_schema = DFSchema::try_from(self.read_schema.as_ref().clone())?;
+                Some(create_physical_expr(
+                    &filter,
+                    &df_schema,
+                    &self.read_schema,
+                    &ExecutionProps::new(),
+                )?)
+            }
+            None => None,
+        };
+
+        let schema = self.read_schema.clone();
+        let default_values = self.default_values.clone();
+        let stream = RecordBatchStreamAdapter::new(
+            self.read_schema.clone(),
+            stream.map(move |batch| {
+                batch.and_then(|batch| {
+                    adapt_batch(batch, &schema, &default_values, predicate.as_ref()).map_err(ArrowError::from)
+                })
+            }),
+        );
+        Ok((Box::pin(stream), existing_columns))
+    }
+
+    // Columns of read schema stored in the file, and those of read schema written in the file
+    fn file_columns(&self, file: &str, file_schema: &Schema) -> Result<(Vec<String>, Vec<String>)> {
+        check_schema_compatible(file_schema, &self.read_schema)?;
+        let cols = self
+            .read_schema
+            .fields()
+            .iter()
+            .map(|f| f.name())
+            .filter(|name| {
+                !self.config.partition_values.contains_key(*name) && file_schema.field_with_name(name).is_ok()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        // Columns missing from files written before they were added are filled with default values,
+        // which take part in merging, only columns left out of a partial upsert are excluded.
+        // Partition values are the same for every row of the file.
+        let existing_columns = self
+            .read_schema
+            .fields()
+            .iter()
+            .map(|f| f.name())
+            .filter(|name| match self.config.file_exist_cols.get(file) {
+                Some(exist_cols) => exist_cols.contains(name) || self.config.partition_values.contains_key(*name),
+                None => true,
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok((cols, existing_columns))
+    }
+
+    // Files are read by the parquet reader directly with their metadata fetched once,
+    // decoding only the leaf columns needed so that struct columns are pruned. Row groups
+    // and pages are skipped by statistics, bloom filters and page indexes, or only rows
+    // of lookup keys are read.
+    async fn read_leaves(
+        &self,
+        file: &str,
+        mut reader: ObjectStoreFileReader,
+        split: Option<&FileSplit>,
+    ) -> Result<(SendableRecordBatchStream, Vec<String>)> {
+        let options = ArrowReaderOptions::new().with_page_index(self.config.page_index_pruning);
+        let builder = ParquetRecordBatchStreamBuilder::new_with_options(reader.clone(), options).await?;
+        let file_schema = builder.schema().clone();
+        let (cols, existing_columns) = self.file_columns(file, &file_schema)?;
+        let leaves = builder
+            .parquet_schema()
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| {
+                let parts = column.path().parts();
+                cols.contains(&parts[0])
+                    && self
+                        .read_schema
+                        .field_with_name(&parts[0])
+                        .map_or(false, |f| leaf_selected(&parts[1..], f.data_type()))
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<usize>>();
+        let mask = ProjectionMask::leaves(builder.parquet_schema(), leaves);
+        // with page indexes loaded if enabled
+        let metadata = builder.metadata().clone();
+        reader.metadata = metadata.clone();
+        let mut row_groups = match split {
+            Some(split) => split.row_groups(&metadata)?,
+            None => (0..metadata.num_row_groups()).collect(),
+        };
+        let mut selection = None;
+        let predicates = column_predicates(&self.pruning_filters);
+        let pruner = FilePruner::new(&metadata, &file_schema, &predicates, &self.metrics);
+        if !pruner.is_empty() {
+            row_groups = pruner.prune_by_statistics(row_groups);
+            if self.config.bloom_filter_pruning {
+                row_groups = pruner
+                    .prune_by_bloom_filters(reader.store.as_ref(), &reader.meta, row_groups)
+                    .await?;
+            }
+            // rows of lookup keys are exactly selected below
+            if self.config.page_index_pruning && self.lookup_keys.is_none() {
+                if let Some((kept, row_selection)) = pruner.select_pages(row_groups.clone()) {
+                    pruner.count_row_groups_pruned(row_groups.len() - kept.len());
+                    row_groups = kept;
+                    selection = Some(row_selection);
+                }
+            }
+        }
+        if let Some(keys) = &self.lookup_keys {
+            let (kept, row_selection) = self.lookup_rows(keys, reader, &file_schema, row_groups.clone()).await?;
+            pruner.count_row_groups_pruned(row_groups.len() - kept.len());
+            row_groups = kept;
+            selection = Some(row_selection);
+        }
+        let builder = builder.with_row_groups(row_groups);
+        let builder = match selection {
+            Some(selection) => builder.with_row_selection(selection),
+            None => builder,
+        };
+        let stream = builder
+            .with_projection(mask)
+            .with_batch_size(self.config.batch_size)
+            .build()?;
+        // projected columns are in the order of the file, with struct fields pruned
+        let stream = RecordBatchStreamAdapter::new(
+            stream.schema().clone(),
+            stream.map(|batch| batch.map_err(ArrowError::from)),
+        );
+        Ok((Box::pin(stream), existing_columns))
+    }
+
+    // Select rows of lookup keys in row groups by binary search over primary key columns,
+    // which are read first. Row groups without any of the keys are removed.
+    async fn lookup_rows(
+        &self,
+        keys: &RecordBatch,
+        reader: ObjectStoreFileReader,
+        file_schema: &Schema,
+        row_groups: Vec<usize>,
+    ) -> Result<(Vec<usize>, RowSelection)> {
+        let metadata = reader.metadata.clone();
+        let builder = ParquetRecordBatchStreamBuilder::new(reader).await?;
+        let leaves = builder
+            .parquet_schema()
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| self.config.primary_keys.contains(&column.path().parts()[0]))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<usize>>();
+        let mask = ProjectionMask::leaves(builder.parquet_schema(), leaves);
+        let stream = builder
+            .with_projection(mask)
+            .with_row_groups(row_groups.clone())
+            .with_batch_size(self.config.batch_size)
+            .build()?;
+        let schema = stream.schema().clone();
+        let batches = stream.try_collect::<Vec<_>>().await?;
+        let batch = concat_batches(&schema, &batches)?;
+        // keys of the same types as columns of the file
+        let mut pk_columns = Vec::with_capacity(keys.num_columns());
+        let mut key_columns = Vec::with_capacity(keys.num_columns());
+        for (pk, key_column) in self.config.primary_keys.iter().zip(keys.columns()) {
+            pk_columns.push(batch.column(batch.schema().index_of(pk)?).clone());
+            key_columns.push(cast(key_column, file_schema.field_with_name(pk)?.data_type())?);
+        }
+
+        let mut kept = vec![];
+        let mut selectors = vec![];
+        let mut offset = 0;
+        for rg in row_groups {
+            let num_rows = metadata.row_group(rg).num_rows() as usize;
+            let ranges = key_ranges(
+                &pk_columns
+                    .iter()
+                    .map(|c| c.slice(offset, num_rows))
+                    .collect::<Vec<ArrayRef>>(),
+                &key_columns,
+            )?;
+            offset += num_rows;
+            if ranges.is_empty() {
+                continue;
+            }
+            kept.push(rg);
+            let mut pos = 0;
+            for range in ranges {
+                if range.start > pos {
+                    selectors.push(RowSelector::skip(range.start - pos));
+                }
+                selectors.push(RowSelector::select(range.end - range.start));
+                pos = range.end;
+            }
+            if num_rows > pos {
+                selectors.push(RowSelector::skip(num_rows - pos));
+            }
+        }
+        Ok((kept, RowSelection::from(selectors)))
+    }
+}
+
+// Sorted and disjoint ranges of rows equal to any of sorted keys, in columns sorted by keys
+fn key_ranges(columns: &[ArrayRef], keys: &[ArrayRef]) -> Result<Vec<Range<usize>>> {
+    let num_rows = columns.first().map_or(0, |c| c.len());
+    let num_keys = keys.first().map_or(0, |c| c.len());
+    let comparators = columns
+        .iter()
+        .zip(keys)
+        .map(|(column, key)| build_compare(column.as_ref(), key.as_ref()))
+        .collect::<ArrowResult<Vec<_>>>()?;
+    let compare = |row: usize, key: usize| {
+        comparators
+            .iter()
+            .map(|cmp| cmp(row, key))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    };
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut start = 0;
+    for key in 0..num_keys {
+        // rows before start are less than the key since keys are sorted
+        let less = partition_point(num_rows - start, |i| compare(start + i, key) == Ordering::Less);
+        start += less;
+        let end = start + partition_point(num_rows - start, |i| compare(start + i, key) != Ordering::Greater);
+        if start < end && ranges.last().map_or(true, |r| r.end <= start) {
+            ranges.push(start..end);
+        }
+    }
+    Ok(ranges)
+}
+
+// Index of the first of n items not satisfying pred, which are all after those satisfying it
+fn partition_point(n: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, n);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+// Whether a parquet leaf column at path under a column of data type is read,
+// struct fields are matched by name and other nested types are read entirely
+fn leaf_selected(path: &[String], data_type: &DataType) -> bool {
+    match (path.split_first(), data_type) {
+        (Some((name, rest)), DataType::Struct(fields)) => fields
+            .iter()
+            .find(|f| f.name() == name)
+            .map_or(false, |f| leaf_selected(rest, f.data_type())),
+        _ => true,
+    }
+}
+
+// AsyncFileReader of parquet files in object stores registered in the session
+#[derive(Clone)]
+struct ObjectStoreFileReader {
+    store: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    // metadata fetched when the file is opened, so that the file could be read again without fetching it
+    metadata: Arc<ParquetMetaData>,
+}
+
+impl ObjectStoreFileReader {
+    async fn open(sess_ctx: &SessionContext, file: &str) -> Result<Self> {
+        let url = ListingTableUrl::parse(file)?;
+        let store = sess_ctx.runtime_env().object_store(url.object_store())?;
+        let meta = store.head(url.prefix()).await?;
+        let location = &meta.location;
+        let metadata = fetch_parquet_metadata(
+            |range| {
+                store
+                    .get_range(location, range)
+                    .map_err(|e| ParquetError::General(format!("failed to read {}: {}", location, e)))
+            },
+            meta.size,
+            None,
+        )
+        .await?;
+        Ok(ObjectStoreFileReader {
+            store,
+            meta,
+            metadata: Arc::new(metadata),
+        })
+    }
+}
+
+impl AsyncFileReader for ObjectStoreFileReader {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        self.store
+            .get_range(&self.meta.location, range)
+            .map_err(|e| ParquetError::General(format!("failed to read {}: {}", self.meta.location, e)))
+            .boxed()
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        futures::future::ready(Ok(self.metadata.clone())).boxed()
+    }
+}
+
+fn adapt_batch(
+    batch: RecordBatch,
+    schema: &SchemaRef,
+    default_values: &HashMap<String, ScalarValue>,
+    predicate: Option<&Arc<dyn PhysicalExpr>>,
+) -> Result<RecordBatch> {
+    let batch = uniform_record_batch(batch, schema, default_values)?;
+    match predicate {
+        Some(predicate) => {
+            let mask = predicate.evaluate(&batch)?.into_array(batch.num_rows());
+            Ok(filter_record_batch(&batch, as_boolean_array(&mask))?)
+        }
+        None => Ok(batch),
+    }
+}
+
+// Read all files of an append-only table. At most thread_num files are read at the same time,
+// each buffering up to prefetch_size batches. Batches are emitted in file order if
+// keep_file_order is set, otherwise in the order they are decoded.
+fn read_files_concurrently(
+    file_reader: Arc<FileReader>,
+    filters: Vec<Expr>,
+) -> (Receiver<ArrowResult<RecordBatch>>, JoinHandle<()>) {
+    let parallelism = file_reader.config.thread_num.max(1);
+    let prefetch_size = file_reader.config.prefetch_size.max(1);
+    let filters = Arc::new(filters);
+    let (tx, rx) = tokio::sync::mpsc::channel(prefetch_size);
+    let join_handle = tokio::task::spawn(async move {
+        let files = file_reader.config.files.clone();
+        if file_reader.config.keep_file_order {
+            // each file has its own channel, drained one after another
+            let mut files = files.into_iter();
+            let mut pending = VecDeque::with_capacity(parallelism);
+            loop {
+                while pending.len() < parallelism {
+                    match files.next() {
+                        Some(file) => {
+                            let (file_tx, file_rx) = tokio::sync::mpsc::channel(prefetch_size);
+                            spawn_file_read(file_reader.clone(), file, filters.clone(), file_tx);
+                            pending.push_back(file_rx);
+                        }
+                        None => break,
+                    }
+                }
+                let mut file_rx = match pending.pop_front() {
+                    Some(file_rx) => file_rx,
+                    None => break,
+                };
+                while let Some(batch) = file_rx.recv().await {
+                    if tx.send(batch).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        } else {
+            let mut running = FuturesUnordered::new();
+            for file in files {
+                if running.len() >= parallelism {
+                    running.next().await;
+                }
+                running.push(spawn_file_read(file_reader.clone(), file, filters.clone(), tx.clone()));
+            }
+            while running.next().await.is_some() {}
+        }
+    });
+    (rx, join_handle)
+}
+
+// read one file in a separate task and send its batches to the channel
+fn spawn_file_read(
+    file_reader: Arc<FileReader>,
+    file: String,
+    filters: Arc<Vec<Expr>>,
+    tx: Sender<ArrowResult<RecordBatch>>,
+) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut stream = match file_reader.read(&file, &filters).await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                let _ = tx.send(Err(e.into())).await;
+                return;
+            }
+        };
+        while let Some(batch) = stream.next().await {
+            if tx.send(batch).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
 // Reader will be used in async closure sent to tokio
 // while accessing its mutable methods.
 pub struct SyncSendableMutableLakeSoulReader {
     inner: Arc<AtomicRefCell<Mutex<LakeSoulReader>>>,
     runtime: Arc<Runtime>,
     schema: Option<SchemaRef>,
+    metrics: Arc<PruningMetrics>,
 }
 
 impl SyncSendableMutableLakeSoulReader {
     pub fn new(reader: LakeSoulReader, runtime: Runtime) -> Self {
+        let metrics = reader.pruning_metrics();
         SyncSendableMutableLakeSoulReader {
             inner: Arc::new(AtomicRefCell::new(Mutex::new(reader))),
             runtime: Arc::new(runtime),
             schema: None,
+            metrics,
         }
     }
 
@@ -122,6 +997,10 @@ impl SyncSendableMutableLakeSoulReader {
         self.schema.clone()
     }
 
+    pub fn get_pruning_metrics(&self) -> Arc<PruningMetrics> {
+        self.metrics.clone()
+    }
+
     fn get_runtime(&self) -> Arc<Runtime> {
         self.runtime.clone()
     }
@@ -245,6 +1124,713 @@ mod tests {
         Ok(())
     }
 
+    use arrow::array::{
+        ArrayRef, Int32Array, Int64Array, LargeStringArray, StringArray, StructArray, TimestampMicrosecondArray,
+        TimestampNanosecondArray,
+    };
+    use arrow_schema::{DataType, Field, TimeUnit};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    fn write_parquet_file(path: &std::path::Path, batch: &RecordBatch) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    async fn read_all_batches(reader: &mut LakeSoulReader) -> Result<RecordBatch> {
+        let mut batches = vec![];
+        while let Some(rb) = reader.next_rb().await {
+            batches.push(rb?);
+        }
+        Ok(arrow::compute::concat_batches(reader.schema.as_ref().unwrap(), &batches)?)
+    }
+
+    #[tokio::test]
+    async fn test_reader_merge_on_read() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let base = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2, 3, 5])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["a1", "a2", "a3", "a5"])) as ArrayRef),
+        ])?;
+        let delta = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([2, 4, 5])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["b2", "b4", "b5"])) as ArrayRef),
+        ])?;
+        let base_path = temp_dir.join("base.parquet");
+        let delta_path = temp_dir.join("delta.parquet");
+        write_parquet_file(&base_path, &base)?;
+        write_parquet_file(&delta_path, &delta)?;
+
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![
+                base_path.into_os_string().into_string().unwrap(),
+                delta_path.into_os_string().into_string().unwrap(),
+            ])
+            .with_primary_keys(vec!["id".to_string()])
+            .with_thread_num(1)
+            .with_batch_size(2);
+        let mut reader = LakeSoulReader::new(builder.clone().build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+
+        let expected = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2, 3, 4, 5])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["a1", "b2", "a3", "b4", "b5"])) as ArrayRef),
+        ])?;
+        assert_eq!(expected.num_columns(), actual.num_columns());
+        assert_eq!(expected.num_rows(), actual.num_rows());
+        for i in 0..expected.num_columns() {
+            assert_eq!(expected.column(i).data(), actual.column(i).data());
+        }
+
+        // limit applies to merged and filtered rows
+        let filter = col("value").not_eq(Expr::Literal(ScalarValue::Utf8(Some("a3".to_string()))));
+        let mut reader = LakeSoulReader::new(builder.with_filters(vec![filter]).with_limit(3).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 2, 4]).data());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_merge_cdc() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let base = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef),
+            ("op", Arc::new(StringArray::from(vec!["insert", "insert", "insert"])) as ArrayRef),
+        ])?;
+        let delta = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([2, 3])) as ArrayRef),
+            ("op", Arc::new(StringArray::from(vec!["delete", "update"])) as ArrayRef),
+        ])?;
+        let base_path = temp_dir.join("base.parquet");
+        let delta_path = temp_dir.join("delta.parquet");
+        write_parquet_file(&base_path, &base)?;
+        write_parquet_file(&delta_path, &delta)?;
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![
+                base_path.into_os_string().into_string().unwrap(),
+                delta_path.into_os_string().into_string().unwrap(),
+            ])
+            .with_primary_keys(vec!["id".to_string()])
+            .with_cdc_column("op".to_string());
+
+        let mut reader = LakeSoulReader::new(builder.clone().build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 3]).data());
+        assert_eq!(actual.column(1).data(), StringArray::from(vec!["insert", "update"]).data());
+
+        let mut reader = LakeSoulReader::new(builder.with_cdc_read_mode(CdcReadMode::Changelog).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 2, 2, 3, 3]).data());
+        assert_eq!(
+            actual.column(1).data(),
+            StringArray::from(vec!["insert", "insert", "delete", "insert", "update"]).data()
+        );
+
+        // CDC column of large strings, with batches released after deleted keys
+        let delta = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2])) as ArrayRef),
+            (
+                "op",
+                Arc::new(LargeStringArray::from(vec!["delete", "delete"])) as ArrayRef,
+            ),
+        ])?;
+        let large_path = temp_dir.join("large.parquet");
+        write_parquet_file(&large_path, &delta)?;
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![large_path.into_os_string().into_string().unwrap()])
+            .with_primary_keys(vec!["id".to_string()])
+            .with_cdc_column("op".to_string())
+            .with_batch_size(1)
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.num_rows(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_partial_upsert() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let base = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec!["a1", "a2"])) as ArrayRef),
+            ("b", Arc::new(StringArray::from(vec!["b1", "b2"])) as ArrayRef),
+        ])?;
+        // upsert of column a only
+        let delta_a = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 3])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec!["a1'", "a3"])) as ArrayRef),
+        ])?;
+        // upsert of column b, written with a null column a
+        let delta_b = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([2])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec![None::<&str>])) as ArrayRef),
+            ("b", Arc::new(StringArray::from(vec!["b2'"])) as ArrayRef),
+        ])?;
+        let base_path = temp_dir.join("base.parquet");
+        let delta_a_path = temp_dir.join("delta_a.parquet");
+        let delta_b_path = temp_dir.join("delta_b.parquet");
+        write_parquet_file(&base_path, &base)?;
+        write_parquet_file(&delta_a_path, &delta_a)?;
+        write_parquet_file(&delta_b_path, &delta_b)?;
+        let delta_a_file = delta_a_path.into_os_string().into_string().unwrap();
+        let delta_b_file = delta_b_path.into_os_string().into_string().unwrap();
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![
+                base_path.into_os_string().into_string().unwrap(),
+                delta_a_file.clone(),
+                delta_b_file.clone(),
+            ])
+            .with_primary_keys(vec!["id".to_string()])
+            .with_schema(base.schema())
+            .with_file_exist_cols(delta_a_file, vec!["id".to_string(), "a".to_string()])
+            .with_file_exist_cols(delta_b_file, vec!["id".to_string(), "b".to_string()])
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 2, 3]).data());
+        assert_eq!(
+            actual.column(1).data(),
+            StringArray::from(vec![Some("a1'"), Some("a2"), Some("a3")]).data()
+        );
+        assert_eq!(
+            actual.column(2).data(),
+            StringArray::from(vec![Some("b1"), Some("b2'"), None]).data()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_partial_upsert_default_value() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        // written before column b was added
+        let base = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec!["a1", "a2"])) as ArrayRef),
+        ])?;
+        // upsert of column b only
+        let delta_b = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([2, 3])) as ArrayRef),
+            ("b", Arc::new(StringArray::from(vec!["b2", "b3"])) as ArrayRef),
+        ])?;
+        // upsert of column a only
+        let delta_a = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([4])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec!["a4"])) as ArrayRef),
+        ])?;
+        let mut files = vec![];
+        for (name, batch) in [
+            ("base.parquet", &base),
+            ("delta_b.parquet", &delta_b),
+            ("delta_a.parquet", &delta_a),
+        ] {
+            let path = temp_dir.join(name);
+            write_parquet_file(&path, batch)?;
+            files.push(path.into_os_string().into_string().unwrap());
+        }
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(files.clone())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_schema(table_schema)
+            .with_default_column_value("b".to_string(), "none".to_string())
+            .with_file_exist_cols(files[1].clone(), vec!["id".to_string(), "b".to_string()])
+            .with_file_exist_cols(files[2].clone(), vec!["id".to_string(), "a".to_string()])
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+
+        assert_eq!(
+            actual.column(0).data(),
+            Int64Array::from_iter_values([1, 2, 3, 4]).data()
+        );
+        assert_eq!(
+            actual.column(1).data(),
+            StringArray::from(vec![Some("a1"), Some("a2"), None, Some("a4")]).data()
+        );
+        assert_eq!(
+            actual.column(2).data(),
+            StringArray::from(vec!["none", "b2", "b3", "none"]).data()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_partition_values() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef),
+        ])?;
+        let path = temp_dir.join("part.parquet");
+        write_parquet_file(&path, &batch)?;
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("value", DataType::Utf8, true),
+            Field::new("range", DataType::Int32, true),
+        ]));
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_file(path.into_os_string().into_string().unwrap())
+            .with_schema(schema)
+            .with_partition_desc("range=3");
+
+        let filter = col("range").eq(Expr::Literal(ScalarValue::Int32(Some(3))));
+        let mut reader = LakeSoulReader::new(builder.clone().with_filters(vec![filter]).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 2]).data());
+        assert_eq!(actual.column(2).data(), Int32Array::from_iter_values([3, 3]).data());
+
+        let filter = col("range").eq(Expr::Literal(ScalarValue::Int32(Some(4))));
+        let mut reader = LakeSoulReader::new(builder.with_filters(vec![filter]).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.num_rows(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_union_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let mut files = vec![];
+        for i in 0..4 {
+            let batch = RecordBatch::try_from_iter([(
+                "id",
+                Arc::new(Int64Array::from_iter_values(i * 10..i * 10 + 3)) as ArrayRef,
+            )])?;
+            let path = temp_dir.join(format!("part-{}.parquet", i));
+            write_parquet_file(&path, &batch)?;
+            files.push(path.into_os_string().into_string().unwrap());
+        }
+        let filter = col("id").not_eq(Expr::Literal(ScalarValue::Int64(Some(21))));
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_filters(vec![filter])
+            .with_thread_num(2)
+            .with_prefetch_size(1);
+
+        let mut reader = LakeSoulReader::new(builder.clone().build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(
+            actual.column(0).data(),
+            Int64Array::from_iter_values([0, 1, 2, 10, 11, 12, 20, 22, 30, 31, 32]).data()
+        );
+
+        let mut reader = LakeSoulReader::new(builder.clone().with_limit(5).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(
+            actual.column(0).data(),
+            Int64Array::from_iter_values([0, 1, 2, 10, 11]).data()
+        );
+
+        let mut reader = LakeSoulReader::new(builder.with_keep_file_order(false).build())?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        let mut ids = actual
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .values()
+            .to_vec();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 2, 10, 11, 12, 20, 22, 30, 31, 32]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_file_split() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values(0..6)) as ArrayRef),
+            (
+                "value",
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e", "f"])) as ArrayRef,
+            ),
+        ])?;
+        let path = temp_dir.join("split.parquet");
+        let props = WriterProperties::builder().set_max_row_group_size(2).build();
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        let file_len = std::fs::metadata(&path)?.len() as i64;
+        let file = path.into_os_string().into_string().unwrap();
+
+        let read_split = |split: FileSplit| {
+            let conf = LakeSoulIOConfigBuilder::new()
+                .with_file(file.clone())
+                .with_file_split(file.clone(), split)
+                .with_filters(vec![col("id").not_eq(Expr::Literal(ScalarValue::Int64(Some(3))))])
+                .build();
+            async move {
+                let mut reader = LakeSoulReader::new(conf)?;
+                reader.start().await?;
+                read_all_batches(&mut reader).await
+            }
+        };
+
+        let actual = read_split(FileSplit::RowGroups(vec![1, 2])).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([2, 4, 5]).data());
+
+        // two halves of the file cover all row groups exactly once
+        let first = read_split(FileSplit::ByteRange {
+            start: 0,
+            length: file_len / 2,
+        })
+        .await?;
+        let second = read_split(FileSplit::ByteRange {
+            start: file_len / 2,
+            length: file_len - file_len / 2,
+        })
+        .await?;
+        assert_eq!(first.num_rows() + second.num_rows(), 5);
+
+        assert!(read_split(FileSplit::RowGroups(vec![3])).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_schema_evolution() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let base = RecordBatch::try_from_iter([
+            ("value", Arc::new(StringArray::from(vec!["a1", "a2"])) as ArrayRef),
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+        ])?;
+        let delta = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([2])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["b2"])) as ArrayRef),
+            ("added", Arc::new(StringArray::from(vec!["c2"])) as ArrayRef),
+        ])?;
+        let base_path = temp_dir.join("base.parquet");
+        let delta_path = temp_dir.join("delta.parquet");
+        write_parquet_file(&base_path, &base)?;
+        write_parquet_file(&delta_path, &delta)?;
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("value", DataType::Utf8, true),
+            Field::new("added", DataType::Utf8, true),
+        ]));
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![
+                base_path.into_os_string().into_string().unwrap(),
+                delta_path.into_os_string().into_string().unwrap(),
+            ])
+            .with_schema(table_schema.clone())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_default_column_value("added".to_string(), "none".to_string())
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.schema(), table_schema);
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([1, 2]).data());
+        assert_eq!(actual.column(1).data(), StringArray::from(vec!["a1", "b2"]).data());
+        assert_eq!(actual.column(2).data(), StringArray::from(vec!["none", "c2"]).data());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_timestamp_evolution() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let ts = TimestampNanosecondArray::from(vec![Some(1_000_001_000), None]).with_timezone("UTC".to_string());
+        let batch = RecordBatch::try_from_iter([("ts", Arc::new(ts) as ArrayRef)])?;
+        let path = temp_dir.join("timestamp.parquet");
+        write_parquet_file(&path, &batch)?;
+        // timestamps are read in the unit and time zone of the target schema, as Spark does
+        let table_schema = Arc::new(Schema::new(vec![Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            true,
+        )]));
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![path.into_os_string().into_string().unwrap()])
+            .with_schema(table_schema.clone())
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.schema(), table_schema);
+        assert_eq!(
+            actual.column(0).data(),
+            TimestampMicrosecondArray::from(vec![Some(1_000_001), None]).data()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_nested_columns() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let nested = StructArray::from(vec![
+            (
+                Field::new("x", DataType::Int32, true),
+                Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+            ),
+            (
+                Field::new("y", DataType::Utf8, true),
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+            ),
+        ]);
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef),
+            ("s", Arc::new(nested) as ArrayRef),
+        ])?;
+        let path = temp_dir.join("nested.parquet");
+        write_parquet_file(&path, &batch)?;
+        let file = path.into_os_string().into_string().unwrap();
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(batch.schema())
+            .with_columns(vec!["id".to_string(), "s.y".to_string()])
+            .with_filter_str("gt(s.x, 1)".to_string())?
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.schema().field(1).name(), "s.y");
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values([2, 3]).data());
+        assert_eq!(actual.column(1).data(), StringArray::from(vec!["b", "c"]).data());
+
+        // only the selected struct field is decoded
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file])
+            .with_schema(batch.schema())
+            .with_column("s.y".to_string())
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.num_columns(), 1);
+        assert_eq!(actual.column(0).data(), StringArray::from(vec!["a", "b", "c"]).data());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_computed_columns() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef),
+            (
+                "v",
+                Arc::new(Int64Array::from(vec![Some(10), None, Some(30)])) as ArrayRef,
+            ),
+        ])?;
+        let path = temp_dir.join("computed.parquet");
+        write_parquet_file(&path, &batch)?;
+        let file = path.into_os_string().into_string().unwrap();
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(batch.schema())
+            .with_column("id".to_string())
+            .with_sql_projection("coalesce(v, 0) + id", "total".to_string())?
+            .with_sql_projection("upper(name)", "upper_name".to_string())?
+            .with_projection(
+                col("id").gt(Expr::Literal(ScalarValue::Int32(Some(1)))),
+                "big".to_string(),
+            )
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        let names = actual
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "total", "upper_name", "big"]);
+        assert_eq!(actual.column(1).data(), Int64Array::from(vec![11, 2, 33]).data());
+        assert_eq!(actual.column(2).data(), StringArray::from(vec!["A", "B", "C"]).data());
+        assert_eq!(
+            actual.column(3).data(),
+            arrow::array::BooleanArray::from(vec![false, true, true]).data()
+        );
+
+        // computed columns follow all columns if none is selected
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file])
+            .with_schema(batch.schema())
+            .with_sql_projection("upper(name)", "upper_name".to_string())?
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        let names = actual
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "name", "v", "upper_name"]);
+        assert_eq!(actual.column(0).data(), batch.column(0).data());
+        assert_eq!(actual.column(3).data(), StringArray::from(vec!["A", "B", "C"]).data());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_pruning() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let batch = RecordBatch::try_from_iter([("id", Arc::new(Int64Array::from_iter_values(0..100)) as ArrayRef)])?;
+        let path = temp_dir.join("pruning.parquet");
+        // 2 row groups of 5 pages each
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(50)
+            .set_data_pagesize_limit(1)
+            .set_write_batch_size(10)
+            .build();
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![path.into_os_string().into_string().unwrap()])
+            .with_schema(batch.schema())
+            .with_sql_filter("id >= 95")?
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from_iter_values(95..100).data());
+        assert_eq!(reader.pruning_metrics().row_groups_pruned(), 1);
+        assert_eq!(reader.pruning_metrics().pages_pruned(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_invalid_filter() {
+        let mut builder = LakeSoulIOConfigBuilder::new()
+            .with_files(vec!["test/test.parquet".to_string()])
+            .with_schema(Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)])));
+        builder.add_filter_str_deferred("not_a_filter".to_string());
+        assert!(LakeSoulReader::new(builder.build()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reader_output_ordering() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef),
+            ("value", Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef),
+        ])?;
+        let path = temp_dir.join("bucket.parquet");
+        write_parquet_file(&path, &batch)?;
+
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![path.into_os_string().into_string().unwrap()])
+            .with_primary_keys(vec!["id".to_string()])
+            .with_hash_bucket_num(4);
+        let mut reader = LakeSoulReader::new(builder.clone().with_hash_bucket_id(2).build())?;
+        reader.start().await?;
+        assert_eq!(reader.hash_bucket_id(), Some(2));
+        let ordering = reader.output_ordering().unwrap();
+        assert_eq!(ordering.len(), 1);
+        assert_eq!(
+            ordering[0].expr.as_any().downcast_ref::<Column>(),
+            Some(&Column::new("id", 0))
+        );
+        let metadata = reader.schema.as_ref().unwrap().metadata().clone();
+        assert_eq!(metadata.get("lakesoul.hash_bucket_id"), Some(&"2".to_string()));
+        assert_eq!(metadata.get("lakesoul.sort_columns"), Some(&"id".to_string()));
+        read_all_batches(&mut reader).await?;
+
+        // ordering refers to columns of the output
+        let reader_conf = builder
+            .clone()
+            .with_columns(vec!["value".to_string(), "id".to_string()])
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let ordering = reader.output_ordering().unwrap();
+        assert_eq!(
+            ordering[0].expr.as_any().downcast_ref::<Column>(),
+            Some(&Column::new("id", 1))
+        );
+        let metadata = reader.schema.as_ref().unwrap().metadata().clone();
+        assert_eq!(metadata.get("lakesoul.sort_columns"), Some(&"id".to_string()));
+        read_all_batches(&mut reader).await?;
+
+        // rows are not sorted by columns projected when primary keys are not
+        let mut reader = LakeSoulReader::new(builder.clone().with_column("value".to_string()).build())?;
+        reader.start().await?;
+        assert!(reader.output_ordering().is_none());
+        let metadata = reader.schema.as_ref().unwrap().metadata().clone();
+        assert_eq!(metadata.get("lakesoul.sort_columns"), None);
+        read_all_batches(&mut reader).await?;
+
+        let mut reader = LakeSoulReader::new(builder.with_hash_bucket_id(4).build())?;
+        assert!(reader.start().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_lookup() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let base = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values(0..100)) as ArrayRef),
+            ("value", Arc::new(Int32Array::from(vec![1; 100])) as ArrayRef),
+        ])?;
+        let delta = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int64Array::from_iter_values((0..100).step_by(2))) as ArrayRef,
+            ),
+            ("value", Arc::new(Int32Array::from(vec![2; 50])) as ArrayRef),
+        ])?;
+        // row groups of 10 rows
+        let props = WriterProperties::builder().set_max_row_group_size(10).build();
+        let mut files = vec![];
+        for (name, batch) in [("base.parquet", &base), ("delta.parquet", &delta)] {
+            let path = temp_dir.join(name);
+            let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), Some(props.clone()))?;
+            writer.write(batch)?;
+            writer.close()?;
+            files.push(path.into_os_string().into_string().unwrap());
+        }
+
+        let keys =
+            RecordBatch::try_from_iter([("id", Arc::new(Int32Array::from(vec![50, 4, 3, 200, 4])) as ArrayRef)])?;
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_schema(base.schema())
+            .with_primary_keys(vec!["id".to_string()]);
+        let reader_conf = builder.clone().with_lookup_keys(keys).build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let actual = read_all_batches(&mut reader).await?;
+        assert_eq!(actual.column(0).data(), Int64Array::from(vec![3, 4, 50]).data());
+        assert_eq!(actual.column(1).data(), Int32Array::from(vec![1, 2, 2]).data());
+        // 8 of 10 row groups of base and 3 of 5 of delta don't have the keys
+        assert_eq!(reader.pruning_metrics().row_groups_pruned(), 11);
+
+        let keys = RecordBatch::try_from_iter([("value", Arc::new(Int32Array::from(vec![1])) as ArrayRef)])?;
+        // keys must be of primary keys
+        let reader_conf = builder.with_lookup_keys(keys).build();
+        assert!(LakeSoulReader::new(reader_conf)?.start().await.is_err());
+        Ok(())
+    }
+
     use tokio::time::{sleep, Duration};
 
     #[tokio::test]
@@ -328,7 +1914,7 @@ mod tests {
         Ok(())
     }
 
-    use crate::lakesoul_io_config::LakeSoulIOConfigBuilder;
+    use crate::lakesoul_io_config::{CdcReadMode, LakeSoulIOConfigBuilder};
     use datafusion::logical_expr::{col, Expr};
     use datafusion_common::ScalarValue;
 
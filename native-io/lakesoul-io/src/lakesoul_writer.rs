@@ -14,11 +14,19 @@
  * limitations under the License.
  */
 
-use crate::lakesoul_io_config::{create_session_context, IOSchema, LakeSoulIOConfig};
+use crate::column_path::split_root;
+use crate::hash_bucket::hash_bucket_ids;
+use crate::lakesoul_io_config::{
+    create_session_context, escape_partition_value, IOSchema, LakeSoulIOConfig, LAKESOUL_EMPTY_STRING,
+    LAKESOUL_NULL_STRING,
+};
 use crate::lakesoul_reader::ArrowResult;
-use arrow::compute::SortOptions;
+use crate::pruning::{statistics_min_max, StatValue};
+use arrow::array::{Array, ArrayRef, UInt32Array};
+use arrow::compute::{take, SortOptions};
 use arrow::record_batch::RecordBatch;
-use arrow_schema::SchemaRef;
+use arrow::util::display::array_value_to_string;
+use arrow_schema::{Schema, SchemaRef};
 use async_trait::async_trait;
 use atomic_refcell::AtomicRefCell;
 use datafusion::datasource::object_store::ObjectStoreUrl;
@@ -35,12 +43,17 @@ use datafusion_common::DataFusionError;
 use datafusion_common::DataFusionError::Internal;
 use object_store::path::Path;
 use object_store::MultipartId;
-use parquet::arrow::ArrowWriter;
-use parquet::basic::Compression;
-use parquet::file::properties::WriterProperties;
+use parquet::arrow::{arrow_to_parquet_schema, ArrowWriter};
+use parquet::basic::{Compression, ConvertedType, Encoding, Type as PhysicalType};
+use parquet::errors::Result as ParquetResult;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use parquet::file::statistics::Statistics;
+use parquet::format::FileMetaData;
+use parquet::schema::types::{ColumnDescriptor, ColumnPath};
 use std::any::Any;
 use std::borrow::Borrow;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::io::ErrorKind::ResourceBusy;
 use std::io::Write;
 use std::sync::Arc;
@@ -53,11 +66,131 @@ use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 use url::{ParseError, Url};
 
+/// A parquet file completely written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenFile {
+    pub path: String,
+    /// like `date=2023-01-01,region=cn`, or `-5` for tables without range partitions
+    pub partition_desc: String,
+    /// bytes of the file
+    pub size: u64,
+    pub num_rows: u64,
+    pub num_row_groups: usize,
+    /// top level columns written in the file
+    pub columns: Vec<String>,
+    /// statistics of each leaf column
+    pub column_statistics: Vec<ColumnStatistics>,
+}
+
+/// Statistics of a leaf column over all row groups of a written file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStatistics {
+    /// path of the leaf column, like `a.b` for nested columns
+    pub column: String,
+    /// min and max of parquet physical values of int, float and utf8 columns,
+    /// None if unknown or all values are null
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// None if statistics are not written
+    pub null_count: Option<u64>,
+}
+
+impl WrittenFile {
+    // metadata of a file closed by arrow writer
+    fn try_new(
+        path: String,
+        partition_desc: String,
+        size: u64,
+        schema: &Schema,
+        metadata: FileMetaData,
+    ) -> Result<Self> {
+        let descr = Arc::new(arrow_to_parquet_schema(schema)?);
+        let row_groups = metadata
+            .row_groups
+            .into_iter()
+            .map(|rg| RowGroupMetaData::from_thrift(descr.clone(), rg))
+            .collect::<ParquetResult<Vec<_>>>()?;
+        let column_statistics = descr
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(leaf, column)| {
+                ColumnStatistics::from_row_groups(column, row_groups.iter().map(|rg| rg.column(leaf).statistics()))
+            })
+            .collect();
+        Ok(WrittenFile {
+            path,
+            partition_desc,
+            size,
+            num_rows: metadata.num_rows as u64,
+            num_row_groups: row_groups.len(),
+            columns: schema.fields().iter().map(|f| f.name().clone()).collect(),
+            column_statistics,
+        })
+    }
+}
+
+impl ColumnStatistics {
+    fn from_row_groups<'a>(
+        column: &ColumnDescriptor,
+        row_groups: impl Iterator<Item = Option<&'a Statistics>>,
+    ) -> Self {
+        let mut null_count = Some(0);
+        let mut min_max: Option<(StatValue, StatValue)> = None;
+        let mut min_max_known = true;
+        for stats in row_groups {
+            let stats = match stats {
+                Some(stats) => stats,
+                None => {
+                    null_count = None;
+                    min_max_known = false;
+                    continue;
+                }
+            };
+            null_count = null_count.map(|n| n + stats.null_count());
+            // all values of the row group are null
+            if !stats.has_min_max_set() {
+                continue;
+            }
+            match statistics_min_max(stats) {
+                Some((min, max)) => {
+                    min_max = Some(match min_max {
+                        Some((prev_min, prev_max)) => (
+                            if min < prev_min { min } else { prev_min },
+                            if max > prev_max { max } else { prev_max },
+                        ),
+                        None => (min, max),
+                    })
+                }
+                None => min_max_known = false,
+            }
+        }
+        let utf8 = column.converted_type() == ConvertedType::UTF8;
+        let to_string = |value: StatValue| match value {
+            StatValue::Int(v) => Some(v.to_string()),
+            StatValue::Float(v) => Some(v.to_string()),
+            StatValue::Bytes(v) if utf8 => String::from_utf8(v).ok(),
+            StatValue::Bytes(_) => None,
+        };
+        let (min, max) = match min_max {
+            Some((min, max)) if min_max_known => (to_string(min), to_string(max)),
+            _ => (None, None),
+        };
+        ColumnStatistics {
+            column: column.path().string(),
+            min,
+            max,
+            null_count,
+        }
+    }
+}
+
 #[async_trait]
 pub trait AsyncBatchWriter {
     async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()>;
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()>;
+    /// Flush remaining rows and complete all files, which are returned
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<WrittenFile>>;
 }
 
 /// An async writer using object_store's multi-part upload feature for cloud storage.
@@ -67,6 +200,8 @@ pub trait AsyncBatchWriter {
 /// by object_store, which would drain and copy the content of the VecDeque so that we could reuse it.
 /// The `CloudMultiPartUpload` itself would try to concurrently upload parts, and
 /// all parts will be committed to cloud storage by shutdown the `AsyncWrite` object.
+/// When max file size or rows is reached, the file is completed and following rows are
+/// written to the next file in the same directory.
 pub struct MultiPartAsyncWriter {
     in_mem_buf: InMemBuf,
     sess_ctx: SessionContext,
@@ -75,6 +210,12 @@ pub struct MultiPartAsyncWriter {
     multi_part_id: MultipartId,
     arrow_writer: ArrowWriter<InMemBuf>,
     config: LakeSoulIOConfig,
+    // the file being written, and bytes uploaded and rows written of it
+    file_name: String,
+    file_bytes: u64,
+    file_rows: usize,
+    // files completed before the current one
+    written_files: Vec<WrittenFile>,
 }
 
 /// Wrap the above async writer with a SortExec to
@@ -82,7 +223,7 @@ pub struct MultiPartAsyncWriter {
 pub struct SortAsyncWriter {
     sorter_sender: Sender<ArrowResult<RecordBatch>>,
     sort_exec: Arc<dyn ExecutionPlan>,
-    join_handle: JoinHandle<Result<()>>,
+    join_handle: JoinHandle<Result<Vec<WrittenFile>>>,
 }
 
 /// A VecDeque which is both std::io::Write and bytes::Buf
@@ -121,6 +262,8 @@ pub struct ReceiverStreamExec {
     stream: AtomicRefCell<Option<tokio::sync::mpsc::Receiver<ArrowResult<RecordBatch>>>>,
     join_handle: AtomicRefCell<Option<JoinHandle<()>>>,
     schema: SchemaRef,
+    // order of rows received, if they are known to be sorted
+    output_ordering: Option<Vec<PhysicalSortExpr>>,
 }
 
 impl ReceiverStreamExec {
@@ -133,8 +276,15 @@ impl ReceiverStreamExec {
             stream: AtomicRefCell::new(Some(receiver)),
             join_handle: AtomicRefCell::new(Some(join_handle)),
             schema,
+            output_ordering: None,
         }
     }
+
+    /// Declare that rows are received in the order of sort expressions
+    pub fn with_output_ordering(mut self, output_ordering: Vec<PhysicalSortExpr>) -> Self {
+        self.output_ordering = Some(output_ordering);
+        self
+    }
 }
 
 impl ExecutionPlan for ReceiverStreamExec {
@@ -151,15 +301,15 @@ impl ExecutionPlan for ReceiverStreamExec {
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
-        None
+        self.output_ordering.as_deref()
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
-        unimplemented!()
+        vec![]
     }
 
-    fn with_new_children(self: Arc<Self>, children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
-        unimplemented!()
+    fn with_new_children(self: Arc<Self>, _: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
     }
 
     fn execute(&self, partition: usize, context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
@@ -174,17 +324,49 @@ impl ExecutionPlan for ReceiverStreamExec {
     }
 }
 
+type OpenedFile = (
+    MultipartId,
+    Box<dyn AsyncWrite + Unpin + Send>,
+    InMemBuf,
+    ArrowWriter<InMemBuf>,
+);
+
 impl MultiPartAsyncWriter {
     pub async fn try_new(mut config: LakeSoulIOConfig) -> Result<Self> {
         if config.files.len() != 1 {
             return Err(Internal("wrong number of file names provided for writer".to_string()));
         }
+        if config.max_file_rows == Some(0) {
+            return Err(Internal("max file rows should be positive".to_string()));
+        }
         let sess_ctx = create_session_context(&mut config)?;
-        let file_name = &config.files[0];
+        let file_name = config.files[0].clone();
+        let (multipart_id, async_writer, in_mem_buf, arrow_writer) =
+            MultiPartAsyncWriter::open_file(&config, &sess_ctx, &file_name).await?;
+        let schema: SchemaRef = config.schema.0.clone();
+
+        Ok(MultiPartAsyncWriter {
+            in_mem_buf,
+            sess_ctx,
+            schema,
+            writer: async_writer,
+            multi_part_id: multipart_id,
+            arrow_writer,
+            config,
+            file_name,
+            file_bytes: 0,
+            file_rows: 0,
+            written_files: vec![],
+        })
+    }
+
+    // start the multi part upload of a file, and the arrow writer of it
+    async fn open_file(config: &LakeSoulIOConfig, sess_ctx: &SessionContext, file_name: &str) -> Result<OpenedFile> {
+        let props = writer_properties(config)?;
 
         // parse file name. Url::parse requires file:// scheme for local files, otherwise
         // RelativeUrlWithoutBase would be throw, in this case we directly return local object store
-        let (object_store, path) = match Url::parse(file_name.as_str()) {
+        let (object_store, path) = match Url::parse(file_name) {
             Ok(url) => Ok((
                 sess_ctx
                     .runtime_env()
@@ -195,7 +377,7 @@ impl MultiPartAsyncWriter {
                 sess_ctx
                     .runtime_env()
                     .object_store(ObjectStoreUrl::local_filesystem())?,
-                Path::from(file_name.as_str()),
+                Path::from(file_name),
             )),
             Err(e) => Err(DataFusionError::External(Box::new(e))),
         }?;
@@ -206,45 +388,82 @@ impl MultiPartAsyncWriter {
         ))));
         let schema: SchemaRef = config.schema.0.clone();
 
-        let arrow_writer = ArrowWriter::try_new(
-            in_mem_buf.clone(),
-            schema.clone(),
-            Some(
-                WriterProperties::builder()
-                    .set_max_row_group_size(config.max_row_group_size)
-                    .set_write_batch_size(config.batch_size)
-                    .set_compression(Compression::SNAPPY)
-                    .build(),
-            ),
-        )?;
+        let arrow_writer = ArrowWriter::try_new(in_mem_buf.clone(), schema, Some(props))?;
 
-        Ok(MultiPartAsyncWriter {
-            in_mem_buf: in_mem_buf.clone(),
-            sess_ctx,
-            schema: schema.clone(),
-            writer: async_writer,
-            multi_part_id: multipart_id,
-            arrow_writer,
-            config,
-        })
+        Ok((multipart_id, async_writer, in_mem_buf, arrow_writer))
     }
 
+    // write a batch and upload row groups flushed, returns bytes uploaded
     async fn write_batch(
         batch: RecordBatch,
         arrow_writer: &mut ArrowWriter<InMemBuf>,
         in_mem_buf: &mut InMemBuf,
         writer: &mut Box<dyn AsyncWrite + Unpin + Send>,
-    ) -> Result<()> {
+    ) -> Result<usize> {
         arrow_writer.write(&batch)?;
         let mut v = in_mem_buf
             .0
             .try_borrow_mut()
             .map_err(|e| Internal(format!("{:?}", e)))?;
-        if v.len() > 0 {
-            MultiPartAsyncWriter::write_part(writer, &mut *v).await
-        } else {
-            Ok(())
+        let len = v.len();
+        if len > 0 {
+            MultiPartAsyncWriter::write_part(writer, &mut *v).await?;
+        }
+        Ok(len)
+    }
+
+    // flush remaining rows and footer of the file, and complete the upload,
+    // returns bytes uploaded and metadata of the file
+    async fn complete_file(
+        arrow_writer: ArrowWriter<InMemBuf>,
+        in_mem_buf: InMemBuf,
+        mut writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<(usize, FileMetaData)> {
+        let metadata = arrow_writer.close()?;
+        let mut v = in_mem_buf
+            .0
+            .try_borrow_mut()
+            .map_err(|e| Internal(format!("{:?}", e)))?;
+        let len = v.len();
+        if len > 0 {
+            MultiPartAsyncWriter::write_part(&mut writer, &mut *v).await?;
         }
+        writer.shutdown().await?;
+        Ok((len, metadata))
+    }
+
+    fn should_roll(&self) -> bool {
+        self.file_rows > 0
+            && (self
+                .config
+                .max_file_rows
+                .map_or(false, |max_rows| self.file_rows >= max_rows)
+                || self
+                    .config
+                    .max_file_size
+                    .map_or(false, |max_size| self.file_bytes >= max_size))
+    }
+
+    // complete the current file and continue with the next one
+    async fn roll(&mut self) -> Result<()> {
+        let file_name = rolling_file_name(&self.config.files[0], self.written_files.len() + 1);
+        let (multi_part_id, writer, in_mem_buf, arrow_writer) =
+            MultiPartAsyncWriter::open_file(&self.config, &self.sess_ctx, &file_name).await?;
+        self.multi_part_id = multi_part_id;
+        let writer = std::mem::replace(&mut self.writer, writer);
+        let in_mem_buf = std::mem::replace(&mut self.in_mem_buf, in_mem_buf);
+        let arrow_writer = std::mem::replace(&mut self.arrow_writer, arrow_writer);
+        let (len, metadata) = MultiPartAsyncWriter::complete_file(arrow_writer, in_mem_buf, writer).await?;
+        self.written_files.push(WrittenFile::try_new(
+            std::mem::replace(&mut self.file_name, file_name),
+            self.config.partition_desc(),
+            self.file_bytes + len as u64,
+            &self.schema,
+            metadata,
+        )?);
+        self.file_bytes = 0;
+        self.file_rows = 0;
+        Ok(())
     }
 
     pub async fn write_part(
@@ -259,25 +478,44 @@ impl MultiPartAsyncWriter {
 #[async_trait]
 impl AsyncBatchWriter for MultiPartAsyncWriter {
     async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
-        MultiPartAsyncWriter::write_batch(batch, &mut self.arrow_writer, &mut self.in_mem_buf, &mut self.writer).await
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            if self.should_roll() {
+                self.roll().await?;
+            }
+            // split the batch at max file rows
+            let len = match self.config.max_file_rows {
+                Some(max_rows) => (max_rows - self.file_rows).min(batch.num_rows() - offset),
+                None => batch.num_rows() - offset,
+            };
+            let bytes = MultiPartAsyncWriter::write_batch(
+                batch.slice(offset, len),
+                &mut self.arrow_writer,
+                &mut self.in_mem_buf,
+                &mut self.writer,
+            )
+            .await?;
+            self.file_bytes += bytes as u64;
+            self.file_rows += len;
+            offset += len;
+        }
+        Ok(())
     }
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()> {
-        // close arrow writer to flush remaining rows
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<WrittenFile>> {
+        // close arrow writer to flush remaining rows, and shutdown multi part async writer
+        // to complete the upload
         let mut this = *self;
-        let arrow_writer = this.arrow_writer;
-        arrow_writer.close()?;
-        let mut v = this
-            .in_mem_buf
-            .0
-            .try_borrow_mut()
-            .map_err(|e| Internal(format!("{:?}", e)))?;
-        if v.len() > 0 {
-            MultiPartAsyncWriter::write_part(&mut this.writer, &mut *v).await?;
-        }
-        // shutdown multi part async writer to complete the upload
-        this.writer.shutdown().await?;
-        Ok(())
+        let (len, metadata) =
+            MultiPartAsyncWriter::complete_file(this.arrow_writer, this.in_mem_buf, this.writer).await?;
+        this.written_files.push(WrittenFile::try_new(
+            this.file_name,
+            this.config.partition_desc(),
+            this.file_bytes + len as u64,
+            &this.schema,
+            metadata,
+        )?);
+        Ok(this.written_files)
     }
 }
 
@@ -335,8 +573,7 @@ impl SortAsyncWriter {
                 let batch = batch?;
                 async_writer.write_record_batch(batch).await?;
             }
-            async_writer.flush_and_close().await?;
-            Ok(())
+            async_writer.flush_and_close().await
         });
 
         Ok(SortAsyncWriter {
@@ -356,7 +593,7 @@ impl AsyncBatchWriter for SortAsyncWriter {
             .map_err(|e| DataFusionError::External(Box::new(e)))
     }
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()> {
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<WrittenFile>> {
         let sender = self.sorter_sender;
         drop(sender);
         self.join_handle
@@ -371,44 +608,477 @@ pub struct SyncSendableMutableLakeSoulWriter {
     schema: SchemaRef,
 }
 
-impl SyncSendableMutableLakeSoulWriter {
-    pub fn try_new(config: LakeSoulIOConfig, runtime: Runtime) -> Result<Self> {
-        let runtime = Arc::new(runtime);
-        runtime.clone().block_on(async move {
-            // if aux sort cols exist, we need to adjust the schema of final writer
-            // to exclude all aux sort cols
-            let writer_schema: SchemaRef = if !config.aux_sort_cols.is_empty() {
-                let schema = config.schema.0.clone();
-                let proj_indices = schema
-                    .fields
+/// Route rows to writers of their hash buckets of primary keys, with files given in config
+/// in the order of bucket ids. Writers are created for buckets once they have rows,
+/// so files of buckets without any rows are not written.
+pub struct HashBucketWriter {
+    config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+    hash_bucket_num: usize,
+    writers: Vec<Option<Box<dyn AsyncBatchWriter + Send>>>,
+}
+
+impl HashBucketWriter {
+    pub fn try_new(config: LakeSoulIOConfig, runtime: Arc<Runtime>) -> Result<Self> {
+        let hash_bucket_num = config
+            .hash_bucket_num
+            .ok_or_else(|| Internal("hash bucket num is not set for hash bucket writer".to_string()))?;
+        if config.primary_keys.is_empty() {
+            return Err(Internal("hash buckets require primary keys".to_string()));
+        }
+        if config.files.len() != hash_bucket_num {
+            return Err(Internal(format!(
+                "{} files provided for {} hash buckets",
+                config.files.len(),
+                hash_bucket_num
+            )));
+        }
+        // writers of buckets are created once they have rows, so check properties of files here
+        writer_properties(&config)?;
+        Ok(HashBucketWriter {
+            config,
+            runtime,
+            hash_bucket_num,
+            writers: (0..hash_bucket_num).map(|_| None).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncBatchWriter for HashBucketWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let bucket_ids = hash_bucket_ids(&batch, &self.config.primary_keys, self.hash_bucket_num)?;
+        let mut bucket_rows = vec![vec![]; self.hash_bucket_num];
+        for (row, id) in bucket_ids.into_iter().enumerate() {
+            bucket_rows[id].push(row as u32);
+        }
+        for (id, rows) in bucket_rows.into_iter().enumerate() {
+            if rows.is_empty() {
+                continue;
+            }
+            let bucket_batch = if rows.len() == batch.num_rows() {
+                batch.clone()
+            } else {
+                let indices = UInt32Array::from(rows);
+                let columns = batch
+                    .columns()
                     .iter()
-                    .filter(|f| !config.aux_sort_cols.contains(f.name()))
-                    .map(|f| {
-                        schema
-                            .index_of(f.name().as_str())
-                            .map_err(|e| DataFusionError::ArrowError(e))
+                    .map(|column| take(column.as_ref(), &indices, None))
+                    .collect::<ArrowResult<Vec<_>>>()?;
+                RecordBatch::try_new(batch.schema(), columns)?
+            };
+            if self.writers[id].is_none() {
+                let mut config = self.config.clone();
+                config.files = vec![self.config.files[id].clone()];
+                self.writers[id] = Some(create_file_writer(config, self.runtime.clone()).await?);
+            }
+            if let Some(writer) = self.writers[id].as_mut() {
+                writer.write_record_batch(bucket_batch).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<WrittenFile>> {
+        let mut files = vec![];
+        for writer in self.writers.into_iter().flatten() {
+            files.extend(writer.flush_and_close().await?);
+        }
+        Ok(files)
+    }
+}
+
+/// Write rows to files of their range partitions and hash buckets under the table path,
+/// `{table_path}/{col}={value}/.../{prefix}-{bucket id}.parquet` with values escaped as
+/// Hive does. Range partition columns are not written in files, and writers are created
+/// for files once they have rows.
+/// Writers of all files are kept open until closed, and for tables with primary keys each
+/// of them buffers all its rows to sort them, so memory used grows with the number of
+/// partitions and buckets written. Rows should be grouped by partitions across writers
+/// when writing many partitions.
+pub struct PartitionedLakeSoulWriter {
+    config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+    table_path: String,
+    hash_bucket_num: usize,
+    // columns written in files
+    data_indices: Vec<usize>,
+    // writers of partition values and buckets
+    writers: BTreeMap<(Vec<String>, usize), Box<dyn AsyncBatchWriter + Send>>,
+}
+
+impl PartitionedLakeSoulWriter {
+    pub fn try_new(config: LakeSoulIOConfig, runtime: Arc<Runtime>) -> Result<Self> {
+        let table_path = config
+            .table_path
+            .clone()
+            .ok_or_else(|| Internal("table path is not set for partitioned writer".to_string()))?;
+        let hash_bucket_num = config.hash_bucket_num.unwrap_or(1);
+        if hash_bucket_num == 0 || (hash_bucket_num > 1 && config.primary_keys.is_empty()) {
+            return Err(Internal(format!(
+                "{} hash buckets for primary keys {:?}",
+                hash_bucket_num, config.primary_keys
+            )));
+        }
+        let schema = config.schema.0.clone();
+        for col in &config.range_partitions {
+            schema.index_of(col)?;
+        }
+        let data_indices = (0..schema.fields().len())
+            .filter(|i| !config.range_partitions.contains(schema.field(*i).name()))
+            .collect();
+        let writer = PartitionedLakeSoulWriter {
+            config,
+            runtime,
+            table_path: table_path.trim_end_matches('/').to_string(),
+            hash_bucket_num,
+            data_indices,
+            writers: BTreeMap::new(),
+        };
+        // writers of files are created once they have rows, so check properties of files here
+        writer_properties(&writer.file_config(&[], 0)?)?;
+        Ok(writer)
+    }
+
+    // partition values of each row in the order of range partitions
+    fn partition_values(&self, batch: &RecordBatch) -> Result<Vec<Vec<String>>> {
+        let columns = self
+            .config
+            .range_partitions
+            .iter()
+            .map(|col| Ok(batch.column(batch.schema().index_of(col)?).clone()))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        (0..batch.num_rows())
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        if column.is_null(row) {
+                            return Ok(LAKESOUL_NULL_STRING.to_string());
+                        }
+                        let value = array_value_to_string(column, row)?;
+                        Ok(if value.is_empty() {
+                            LAKESOUL_EMPTY_STRING.to_string()
+                        } else {
+                            value
+                        })
                     })
-                    .collect::<Result<Vec<usize>>>()?;
-                Arc::new(schema.project(proj_indices.borrow())?)
+                    .collect()
+            })
+            .collect()
+    }
+
+    // config of the writer of a file of partition values and bucket
+    fn file_config(&self, values: &[String], bucket: usize) -> Result<LakeSoulIOConfig> {
+        let mut config = self.config.clone();
+        let mut path = self.table_path.clone();
+        for (col, value) in self.config.range_partitions.iter().zip(values) {
+            path.push_str(&format!("/{}={}", col, escape_partition_value(value)));
+            config.partition_values.insert(col.clone(), value.clone());
+        }
+        path.push_str(&format!("/{}-{:05}.parquet", self.config.file_name_prefix, bucket));
+        config.files = vec![path];
+        config.schema = IOSchema(Arc::new(self.config.schema.0.project(&self.data_indices)?));
+        Ok(config)
+    }
+}
+
+#[async_trait]
+impl AsyncBatchWriter for PartitionedLakeSoulWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let partition_values = self.partition_values(&batch)?;
+        let bucket_ids = if self.hash_bucket_num > 1 {
+            hash_bucket_ids(&batch, &self.config.primary_keys, self.hash_bucket_num)?
+        } else {
+            vec![0; batch.num_rows()]
+        };
+        let mut file_rows: BTreeMap<(Vec<String>, usize), Vec<u32>> = BTreeMap::new();
+        for (row, (values, bucket)) in partition_values.into_iter().zip(bucket_ids).enumerate() {
+            file_rows.entry((values, bucket)).or_default().push(row as u32);
+        }
+        let data = batch.project(&self.data_indices)?;
+        for ((values, bucket), rows) in file_rows {
+            let file_batch = if rows.len() == data.num_rows() {
+                data.clone()
             } else {
-                config.schema.0.clone()
+                let indices = UInt32Array::from(rows);
+                let columns = data
+                    .columns()
+                    .iter()
+                    .map(|column| take(column.as_ref(), &indices, None))
+                    .collect::<ArrowResult<Vec<_>>>()?;
+                RecordBatch::try_new(data.schema(), columns)?
             };
+            let key = (values, bucket);
+            if !self.writers.contains_key(&key) {
+                let config = self.file_config(&key.0, bucket)?;
+                let writer = create_file_writer(config, self.runtime.clone()).await?;
+                self.writers.insert(key.clone(), writer);
+            }
+            if let Some(writer) = self.writers.get_mut(&key) {
+                writer.write_record_batch(file_batch).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<WrittenFile>> {
+        let mut files = vec![];
+        for writer in self.writers.into_values() {
+            files.extend(writer.flush_and_close().await?);
+        }
+        Ok(files)
+    }
+}
+
+// Properties of written parquet files
+fn writer_properties(config: &LakeSoulIOConfig) -> Result<WriterProperties> {
+    if !config.option_errors.is_empty() {
+        return Err(DataFusionError::Plan(config.option_errors.join("; ")));
+    }
+    let compression = match &config.compression {
+        Some(codec) => parse_compression(codec)?,
+        None => Compression::SNAPPY,
+    };
+    let mut builder = WriterProperties::builder()
+        .set_max_row_group_size(config.max_row_group_size)
+        .set_write_batch_size(config.batch_size)
+        .set_compression(compression);
+    if let Some(enabled) = config.dictionary_enabled {
+        builder = builder.set_dictionary_enabled(enabled);
+    }
+    // the parquet writer panics on encodings not supported by physical types of columns
+    let parquet_schema = arrow_to_parquet_schema(&config.schema.0)?;
+    if let Some(encoding) = &config.encoding {
+        // a table wide encoding only applies to leaf columns supporting it
+        let parsed = parse_encoding(encoding)?;
+        let leaves = parquet_schema
+            .columns()
+            .iter()
+            .filter(|leaf| encoding_supported(parsed, leaf.physical_type()))
+            .collect::<Vec<_>>();
+        if leaves.is_empty() {
+            return Err(DataFusionError::Plan(format!(
+                "encoding {} is not supported by any column",
+                encoding
+            )));
+        }
+        for leaf in leaves {
+            builder = builder.set_column_encoding(leaf.path().clone(), parsed);
+        }
+    }
+    if let Some(level) = &config.statistics_enabled {
+        builder = builder.set_statistics_enabled(parse_statistics_enabled(level)?);
+    }
+    if let Some(data_page_size) = config.data_page_size {
+        builder = builder.set_data_pagesize_limit(data_page_size);
+    }
+    if let Some(dictionary_page_size) = config.dictionary_page_size {
+        builder = builder.set_dictionary_pagesize_limit(dictionary_page_size);
+    }
+    if let Some(created_by) = &config.created_by {
+        builder = builder.set_created_by(created_by.clone());
+    }
+    for (column, codec) in &config.column_compressions {
+        builder = builder.set_column_compression(column_path(config, column)?, parse_compression(codec)?);
+    }
+    for (column, enabled) in &config.column_dictionary_enabled {
+        builder = builder.set_column_dictionary_enabled(column_path(config, column)?, *enabled);
+    }
+    for (column, encoding) in &config.column_encodings {
+        let path = column_path(config, column)?;
+        let parsed = parse_encoding(encoding)?;
+        if let Some(leaf) = parquet_schema.columns().iter().find(|leaf| leaf.path() == &path) {
+            if !encoding_supported(parsed, leaf.physical_type()) {
+                return Err(DataFusionError::Plan(format!(
+                    "encoding {} is not supported by column {} of physical type {}",
+                    encoding,
+                    column,
+                    leaf.physical_type()
+                )));
+            }
+        }
+        builder = builder.set_column_encoding(path, parsed);
+    }
+    for (column, level) in &config.column_statistics_enabled {
+        builder = builder.set_column_statistics_enabled(column_path(config, column)?, parse_statistics_enabled(level)?);
+    }
+    Ok(builder.build())
+}
+
+// Encodings of values not dictionary encoded, dictionary encodings are only
+// used by enabling dictionary
+fn parse_encoding(encoding: &str) -> Result<Encoding> {
+    match encoding.trim().to_ascii_lowercase().as_str() {
+        "plain" => Ok(Encoding::PLAIN),
+        "rle" => Ok(Encoding::RLE),
+        "delta_binary_packed" => Ok(Encoding::DELTA_BINARY_PACKED),
+        "delta_length_byte_array" => Ok(Encoding::DELTA_LENGTH_BYTE_ARRAY),
+        "delta_byte_array" => Ok(Encoding::DELTA_BYTE_ARRAY),
+        "byte_stream_split" => Ok(Encoding::BYTE_STREAM_SPLIT),
+        "bit_packed" => Err(DataFusionError::Plan(
+            "encoding bit_packed is deprecated and not supported by the parquet writer".to_string(),
+        )),
+        "plain_dictionary" | "rle_dictionary" => Err(DataFusionError::Plan(format!(
+            "encoding {} is used by enabling dictionary",
+            encoding
+        ))),
+        _ => Err(DataFusionError::Plan(format!("unknown encoding {}", encoding))),
+    }
+}
+
+// Whether values of a physical type could be written in an encoding by the parquet writer
+fn encoding_supported(encoding: Encoding, physical_type: PhysicalType) -> bool {
+    match encoding {
+        Encoding::PLAIN => true,
+        Encoding::RLE => physical_type == PhysicalType::BOOLEAN,
+        Encoding::DELTA_BINARY_PACKED => matches!(physical_type, PhysicalType::INT32 | PhysicalType::INT64),
+        Encoding::DELTA_LENGTH_BYTE_ARRAY => physical_type == PhysicalType::BYTE_ARRAY,
+        Encoding::DELTA_BYTE_ARRAY => {
+            matches!(
+                physical_type,
+                PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY
+            )
+        }
+        Encoding::BYTE_STREAM_SPLIT => matches!(physical_type, PhysicalType::FLOAT | PhysicalType::DOUBLE),
+        // dictionary encodings are set by enabling dictionary
+        _ => false,
+    }
+}
+
+fn parse_statistics_enabled(level: &str) -> Result<EnabledStatistics> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "none" => Ok(EnabledStatistics::None),
+        "chunk" => Ok(EnabledStatistics::Chunk),
+        "page" => Ok(EnabledStatistics::Page),
+        _ => Err(DataFusionError::Plan(format!("unknown statistics level {}", level))),
+    }
+}
+
+// Path of a leaf column like `a.b`, the longest top level column name in schema is taken as
+// the root the same as `column_path::ColumnPath::resolve`, followed by names of parquet fields
+fn column_path(config: &LakeSoulIOConfig, column: &str) -> Result<ColumnPath> {
+    let (root, rest) = split_root(column, &config.schema.0)?;
+    let mut parts = vec![root.to_string()];
+    if !rest.is_empty() {
+        let rest = rest
+            .strip_prefix('.')
+            .filter(|r| !r.contains('['))
+            .ok_or_else(|| DataFusionError::Plan(format!("invalid parquet column path {}", column)))?;
+        parts.extend(rest.split('.').map(String::from));
+    }
+    Ok(ColumnPath::new(parts))
+}
+
+// Codecs like `zstd` or with levels like `zstd(19)`. Levels are validated, but could not be
+// set by the parquet writer yet, which always uses the default level of codecs.
+// TODO: set levels once parquet is upgraded to a version with levels in `Compression`
+pub(crate) fn parse_compression(codec: &str) -> Result<Compression> {
+    let lower = codec.trim().to_ascii_lowercase();
+    let (name, level) = match lower.strip_suffix(')').and_then(|c| c.split_once('(')) {
+        Some((name, level)) => {
+            let level = level
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| DataFusionError::Plan(format!("invalid compression level of {}", codec)))?;
+            (name.trim(), Some(level))
+        }
+        None => (lower.as_str(), None),
+    };
+    let (compression, levels) = match name {
+        "uncompressed" | "none" => (Compression::UNCOMPRESSED, None),
+        "snappy" => (Compression::SNAPPY, None),
+        "gzip" => (Compression::GZIP, Some(0..=9)),
+        "lzo" => (Compression::LZO, None),
+        "brotli" => (Compression::BROTLI, Some(0..=11)),
+        "lz4" => (Compression::LZ4, None),
+        "lz4_raw" => (Compression::LZ4_RAW, None),
+        "zstd" => (Compression::ZSTD, Some(1..=22)),
+        _ => return Err(DataFusionError::Plan(format!("unknown compression codec {}", codec))),
+    };
+    match (level, levels) {
+        (None, _) => Ok(compression),
+        (Some(level), Some(levels)) if levels.contains(&level) => Err(DataFusionError::NotImplemented(format!(
+            "compression level of {}, only the default level is supported by the parquet writer",
+            codec
+        ))),
+        (Some(_), _) => Err(DataFusionError::Plan(format!("invalid compression level of {}", codec))),
+    }
+}
+
+// Rolled files are named by their index after the first one,
+// `dir/part-00000.parquet` is followed by `dir/part-00000-00001.parquet`
+fn rolling_file_name(file_name: &str, index: usize) -> String {
+    let name_start = file_name.rfind('/').map_or(0, |i| i + 1);
+    match file_name[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let (stem, extension) = file_name.split_at(name_start + dot);
+            format!("{}-{:05}{}", stem, index, extension)
+        }
+        _ => format!("{}-{:05}", file_name, index),
+    }
+}
+
+// Schema of written files, aux sort cols are only used for sorting and not written
+fn writer_schema(config: &LakeSoulIOConfig) -> Result<SchemaRef> {
+    if config.aux_sort_cols.is_empty() {
+        return Ok(config.schema.0.clone());
+    }
+    let schema = config.schema.0.clone();
+    let proj_indices = schema
+        .fields
+        .iter()
+        .filter(|f| !config.aux_sort_cols.contains(f.name()))
+        .map(|f| {
+            schema
+                .index_of(f.name().as_str())
+                .map_err(|e| DataFusionError::ArrowError(e))
+        })
+        .collect::<Result<Vec<usize>>>()?;
+    Ok(Arc::new(schema.project(proj_indices.borrow())?))
+}
 
-            let mut writer_config = config.clone();
-            writer_config.schema = IOSchema(writer_schema);
-            let writer = MultiPartAsyncWriter::try_new(writer_config).await?;
+// Writer of the only file in config, sorting rows by primary keys if any
+async fn create_file_writer(
+    config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+) -> Result<Box<dyn AsyncBatchWriter + Send>> {
+    let mut writer_config = config.clone();
+    writer_config.schema = IOSchema(writer_schema(&config)?);
+    let writer = MultiPartAsyncWriter::try_new(writer_config).await?;
+    if !config.primary_keys.is_empty() {
+        Ok(Box::new(SortAsyncWriter::try_new(writer, config, runtime)?))
+    } else {
+        Ok(Box::new(writer))
+    }
+}
 
-            let schema = writer.schema.clone();
-            let writer: Box<dyn AsyncBatchWriter> = if !config.primary_keys.is_empty() {
-                Box::new(SortAsyncWriter::try_new(writer, config, runtime.clone())?)
+impl SyncSendableMutableLakeSoulWriter {
+    /// Rows are written to files of their partitions under the table path if given,
+    /// otherwise to one file given in config, or routed to files of their hash buckets
+    /// if more files are given with hash bucket num.
+    pub fn try_new(config: LakeSoulIOConfig, runtime: Runtime) -> Result<Self> {
+        let runtime = Arc::new(runtime);
+        runtime.clone().block_on(async move {
+            // this should be the final written schema
+            let mut schema = writer_schema(&config)?;
+            let writer: Box<dyn AsyncBatchWriter> = if config.table_path.is_some() {
+                // range partition columns are not written either
+                let data_indices = (0..schema.fields().len())
+                    .filter(|i| !config.range_partitions.contains(schema.field(*i).name()))
+                    .collect::<Vec<_>>();
+                schema = Arc::new(schema.project(&data_indices)?);
+                Box::new(PartitionedLakeSoulWriter::try_new(config, runtime.clone())?)
+            } else if config.hash_bucket_num.is_some() && config.files.len() > 1 {
+                Box::new(HashBucketWriter::try_new(config, runtime.clone())?)
             } else {
-                Box::new(writer)
+                create_file_writer(config, runtime.clone()).await?
             };
 
             Ok(SyncSendableMutableLakeSoulWriter {
                 inner: Arc::new(Mutex::new(writer)),
                 runtime,
-                schema, // this should be the final written schema
+                schema,
             })
         })
     }
@@ -426,7 +1096,8 @@ impl SyncSendableMutableLakeSoulWriter {
         })
     }
 
-    pub fn flush_and_close(self) -> Result<()> {
+    /// Complete all files written, which are returned
+    pub fn flush_and_close(self) -> Result<Vec<WrittenFile>> {
         let inner_writer = match Arc::try_unwrap(self.inner) {
             Ok(inner) => inner,
             Err(_) => return Err(Internal("Cannot get ownership of inner writer".to_string())),
@@ -445,15 +1116,22 @@ impl SyncSendableMutableLakeSoulWriter {
 
 #[cfg(test)]
 mod tests {
+    use crate::hash_bucket::hash_bucket_ids;
     use crate::lakesoul_io_config::LakeSoulIOConfigBuilder;
     use crate::lakesoul_reader::LakeSoulReader;
     use crate::lakesoul_writer::{
-        AsyncBatchWriter, MultiPartAsyncWriter, SortAsyncWriter, SyncSendableMutableLakeSoulWriter,
+        column_path, parse_compression, parse_encoding, parse_statistics_enabled, rolling_file_name, AsyncBatchWriter,
+        ColumnStatistics, MultiPartAsyncWriter, SortAsyncWriter, SyncSendableMutableLakeSoulWriter,
     };
-    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray};
     use arrow::record_batch::RecordBatch;
-    use datafusion::error::Result;
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::error::{DataFusionError, Result};
     use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+    use parquet::basic::{Compression, Encoding};
+    use parquet::file::properties::EnabledStatistics;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::schema::types::ColumnPath;
     use std::fs::File;
     use std::sync::Arc;
     use tokio::runtime::Builder;
@@ -588,6 +1266,512 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hash_bucket_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let col = Arc::new(Int64Array::from_iter_values([8, 7, 6, 5, 4, 3, 2, 1])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)])?;
+        let temp_dir = tempfile::tempdir()?.into_path();
+        let files = (0..4)
+            .map(|i| {
+                temp_dir
+                    .join(format!("bucket_{}.parquet", i))
+                    .into_os_string()
+                    .into_string()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(files.clone())
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["col".to_string()])
+            .with_hash_bucket_num(4)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write.clone())?;
+        writer.flush_and_close()?;
+
+        let mut num_rows = 0;
+        for (id, file) in files.iter().enumerate() {
+            let file = match File::open(file) {
+                Ok(file) => file,
+                // no rows in the bucket
+                Err(_) => continue,
+            };
+            for batch in ParquetRecordBatchReader::try_new(file, 1024).unwrap() {
+                let batch = batch?;
+                num_rows += batch.num_rows();
+                let ids = hash_bucket_ids(&batch, &["col".to_string()], 4)?;
+                assert!(ids.iter().all(|i| *i == id));
+                let values = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                assert!(values.values().windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+        assert_eq!(num_rows, to_write.num_rows());
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioned_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let date = Arc::new(StringArray::from(vec![
+            Some("2023-01-01"),
+            Some("2023-01-02"),
+            None,
+            Some("2023-01-01"),
+        ])) as ArrayRef;
+        let id = Arc::new(Int64Array::from_iter_values([4, 3, 2, 1])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("date", date), ("id", id)])?;
+        let table_path = tempfile::tempdir()?.into_path().into_os_string().into_string().unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_range_partitions(vec!["date".to_string()])
+            .with_table_path(table_path.clone())
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write.clone())?;
+        let files = writer.flush_and_close()?;
+
+        let descs = files.iter().map(|f| f.partition_desc.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            descs,
+            vec!["date=2023-01-01", "date=2023-01-02", "date=__L@KE$OUL_NULL__"]
+        );
+        assert_eq!(
+            files[0].path,
+            format!("{}/date=2023-01-01/part-00000.parquet", table_path)
+        );
+        let file = File::open(&files[0].path)?;
+        let batches = ParquetRecordBatchReader::try_new(file, 1024)
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // range partition columns are not written
+        assert_eq!(batches[0].num_columns(), 1);
+        let values = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(values.values(), &[1, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioned_write_escaped() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let region = Arc::new(StringArray::from(vec!["a/b", "x,y=z", "2023-01-01 10:00:00"])) as ArrayRef;
+        let id = Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("region", region), ("id", id)])?;
+        let table_path = tempfile::tempdir()?.into_path().into_os_string().into_string().unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_schema(to_write.schema())
+            .with_range_partitions(vec!["region".to_string()])
+            .with_table_path(table_path.clone())
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let files = writer.flush_and_close()?;
+
+        let descs = files.iter().map(|f| f.partition_desc.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            descs,
+            vec!["region=2023-01-01 10%3A00%3A00", "region=a%2Fb", "region=x%2Cy%3Dz"]
+        );
+        assert_eq!(files[1].path, format!("{}/region=a%2Fb/part-00000.parquet", table_path));
+        // each partition is a single directory
+        for file in &files {
+            assert!(File::open(&file.path).is_ok());
+        }
+        // partition descs are unescaped by readers
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        for (file, value) in files.iter().zip(["2023-01-01 10:00:00", "a/b", "x,y=z"]) {
+            let reader_conf = LakeSoulIOConfigBuilder::new()
+                .with_file(file.path.clone())
+                .with_partition_desc(&file.partition_desc)
+                .build();
+            let mut reader = LakeSoulReader::new(reader_conf)?;
+            let batch = runtime.block_on(async move {
+                reader.start().await?;
+                reader.next_rb().await.unwrap().map_err(DataFusionError::from)
+            })?;
+            let column = batch.column(batch.schema().index_of("region")?);
+            assert_eq!(column.data(), StringArray::from(vec![value]).data());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioned_bucket_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let range = Arc::new(Int64Array::from_iter_values([1, 1, 1, 1, 2, 2, 2, 2])) as ArrayRef;
+        let id = Arc::new(Int64Array::from_iter_values([8, 7, 6, 5, 4, 3, 2, 1])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("range", range), ("id", id)])?;
+        let table_path = tempfile::tempdir()?.into_path().into_os_string().into_string().unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_range_partitions(vec!["range".to_string()])
+            .with_hash_bucket_num(2)
+            .with_table_path(table_path)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write.clone())?;
+        let files = writer.flush_and_close()?;
+
+        let mut num_rows = 0;
+        for written in &files {
+            let bucket = if written.path.ends_with("-00000.parquet") { 0 } else { 1 };
+            for batch in ParquetRecordBatchReader::try_new(File::open(&written.path)?, 1024).unwrap() {
+                let batch = batch?;
+                num_rows += batch.num_rows();
+                let ids = hash_bucket_ids(&batch, &["id".to_string()], 2)?;
+                assert!(ids.iter().all(|i| *i == bucket));
+                let values = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                let expected = if written.partition_desc == "range=1" {
+                    5..=8
+                } else {
+                    1..=4
+                };
+                assert!(values.values().iter().all(|v| expected.contains(v)));
+            }
+        }
+        assert_eq!(num_rows, to_write.num_rows());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolling_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let col = Arc::new(Int64Array::from_iter_values((0..10).rev())) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)])?;
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("part-00000.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["col".to_string()])
+            .with_max_file_rows(4)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let files = writer.flush_and_close()?;
+
+        let paths = files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                file.clone(),
+                file.replace(".parquet", "-00001.parquet"),
+                file.replace(".parquet", "-00002.parquet")
+            ]
+        );
+        let mut values = vec![];
+        for (path, num_rows) in paths.iter().zip([4, 4, 2]) {
+            let batches = ParquetRecordBatchReader::try_new(File::open(path)?, 1024)
+                .unwrap()
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), num_rows);
+            for batch in batches {
+                let col = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                values.extend_from_slice(col.values());
+            }
+        }
+        // rows are sorted across rolled files
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolling_write_by_size() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("part-00000.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Int64, false)]));
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file])
+            .with_schema(schema)
+            .with_max_row_group_size(2)
+            .with_max_file_size(1)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        for i in 0..3 {
+            let col = Arc::new(Int64Array::from_iter_values([i * 2, i * 2 + 1])) as ArrayRef;
+            writer.write_batch(RecordBatch::try_from_iter([("col", col)])?)?;
+        }
+        let files = writer.flush_and_close()?;
+        // every row group flushed reaches max file size
+        assert_eq!(files.len(), 3);
+        for written in files {
+            let reader = ParquetRecordBatchReader::try_new(File::open(written.path)?, 1024).unwrap();
+            assert_eq!(reader.map(|b| b.unwrap().num_rows()).sum::<usize>(), 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_written_file_metadata() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let id = Arc::new(Int64Array::from_iter_values([3, 1, 2])) as ArrayRef;
+        let name = Arc::new(StringArray::from(vec![Some("b"), None, Some("a")])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("id", id), ("name", name)])?;
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("test.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_max_row_group_size(2)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let files = writer.flush_and_close()?;
+
+        assert_eq!(files.len(), 1);
+        let written = &files[0];
+        assert_eq!(written.path, file);
+        assert_eq!(written.partition_desc, "-5");
+        assert_eq!(written.size, std::fs::metadata(&file)?.len());
+        assert_eq!(written.num_rows, 3);
+        assert_eq!(written.num_row_groups, 2);
+        assert_eq!(written.columns, vec!["id".to_string(), "name".to_string()]);
+        assert_eq!(
+            written.column_statistics,
+            vec![
+                ColumnStatistics {
+                    column: "id".to_string(),
+                    min: Some("1".to_string()),
+                    max: Some("3".to_string()),
+                    null_count: Some(0),
+                },
+                ColumnStatistics {
+                    column: "name".to_string(),
+                    min: Some("a".to_string()),
+                    max: Some("b".to_string()),
+                    null_count: Some(1),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_compression() {
+        assert_eq!(parse_compression("ZSTD").unwrap(), Compression::ZSTD);
+        assert_eq!(parse_compression("none").unwrap(), Compression::UNCOMPRESSED);
+        assert_eq!(parse_compression("lz4_raw").unwrap(), Compression::LZ4_RAW);
+        assert!(matches!(
+            parse_compression("zstd(19)"),
+            Err(DataFusionError::NotImplemented(_))
+        ));
+        assert!(matches!(parse_compression("zstd(23)"), Err(DataFusionError::Plan(_))));
+        assert!(matches!(parse_compression("snappy(1)"), Err(DataFusionError::Plan(_))));
+        assert!(matches!(parse_compression("xz"), Err(DataFusionError::Plan(_))));
+    }
+
+    #[test]
+    fn test_compression_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let id = Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef;
+        let name = Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("id", id), ("name", name)])?;
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("test.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(to_write.schema())
+            .with_object_store_option("parquet.compression".to_string(), "zstd".to_string())
+            .with_column_compression("name".to_string(), "lz4_raw".to_string())?
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        writer.flush_and_close()?;
+
+        let reader = SerializedFileReader::new(File::open(&file)?)?;
+        let row_group = reader.metadata().row_group(0);
+        assert_eq!(row_group.column(0).compression(), Compression::ZSTD);
+        assert_eq!(row_group.column(1).compression(), Compression::LZ4_RAW);
+
+        // invalid codecs are reported by the builder
+        let mut builder = LakeSoulIOConfigBuilder::new().with_schema(to_write.schema());
+        assert!(matches!(
+            builder.set_compression("xz".to_string()),
+            Err(DataFusionError::Plan(_))
+        ));
+        assert!(matches!(
+            builder.set_column_compression("name".to_string(), "zstd(19)".to_string()),
+            Err(DataFusionError::NotImplemented(_))
+        ));
+        // and codecs of options by partitioned writers before any file is written
+        let table_path = tempfile::tempdir()?.into_path().into_os_string().into_string().unwrap();
+        let writer_conf = builder
+            .with_table_path(table_path)
+            .with_object_store_option("parquet.compression#name".to_string(), "xz".to_string())
+            .build();
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        assert!(matches!(
+            SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime),
+            Err(DataFusionError::Plan(_))
+        ));
+        // levels are rejected in options as well
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file])
+            .with_schema(to_write.schema())
+            .with_object_store_option("parquet.compression".to_string(), "gzip(6)".to_string())
+            .build();
+        assert_eq!(writer_conf.option_errors.len(), 1);
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        assert!(SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_path() {
+        let s = DataType::Struct(vec![Field::new("x", DataType::Int64, true)]);
+        let schema = Schema::new(vec![Field::new("a.b", DataType::Int64, true), Field::new("s", s, true)]);
+        let config = LakeSoulIOConfigBuilder::new().with_schema(Arc::new(schema)).build();
+        // top level columns with dots take precedence over nested paths
+        assert_eq!(
+            column_path(&config, "a.b").unwrap(),
+            ColumnPath::new(vec!["a.b".to_string()])
+        );
+        assert_eq!(
+            column_path(&config, "s.x").unwrap(),
+            ColumnPath::new(vec!["s".to_string(), "x".to_string()])
+        );
+        assert!(matches!(column_path(&config, "s[1]"), Err(DataFusionError::Plan(_))));
+        assert!(matches!(column_path(&config, "t"), Err(DataFusionError::Plan(_))));
+    }
+
+    #[test]
+    fn test_parse_writer_properties() {
+        assert_eq!(
+            parse_encoding("DELTA_BINARY_PACKED").unwrap(),
+            Encoding::DELTA_BINARY_PACKED
+        );
+        assert!(matches!(
+            parse_encoding("rle_dictionary"),
+            Err(DataFusionError::Plan(_))
+        ));
+        assert!(matches!(parse_encoding("zigzag"), Err(DataFusionError::Plan(_))));
+        assert_eq!(parse_statistics_enabled("chunk").unwrap(), EnabledStatistics::Chunk);
+        assert!(matches!(parse_statistics_enabled("all"), Err(DataFusionError::Plan(_))));
+    }
+
+    #[test]
+    fn test_writer_properties_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let id = Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef;
+        let name = Arc::new(StringArray::from_iter_values(
+            (0..1000).map(|i| format!("name_{}", i % 10)),
+        )) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("id", id), ("name", name)])?;
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("test.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(to_write.schema())
+            .with_column_dictionary_enabled("id".to_string(), false)
+            .with_column_encoding("id".to_string(), "delta_binary_packed".to_string())
+            .with_column_statistics_enabled("name".to_string(), "none".to_string())
+            .with_data_page_size(1024)
+            .with_created_by("lakesoul test".to_string())
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let files = writer.flush_and_close()?;
+        // statistics of name are not written
+        assert_eq!(files[0].column_statistics[0].null_count, Some(0));
+        assert_eq!(files[0].column_statistics[1].null_count, None);
+
+        let reader = SerializedFileReader::new(File::open(&file)?)?;
+        assert_eq!(reader.metadata().file_metadata().created_by(), Some("lakesoul test"));
+        let row_group = reader.metadata().row_group(0);
+        let id_encodings = row_group.column(0).encodings();
+        assert!(id_encodings.contains(&Encoding::DELTA_BINARY_PACKED));
+        assert!(!id_encodings.contains(&Encoding::RLE_DICTIONARY));
+        assert!(row_group.column(1).encodings().contains(&Encoding::RLE_DICTIONARY));
+        assert!(row_group.column(1).statistics().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mixed_type_encoding_write() -> Result<()> {
+        let id = Arc::new(Int64Array::from_iter_values([1, 2, 3])) as ArrayRef;
+        let name = Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef;
+        let score = Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("id", id), ("name", name), ("score", score)])?;
+        let file = tempfile::tempdir()?
+            .into_path()
+            .join("test.parquet")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![file.clone()])
+            .with_schema(to_write.schema())
+            .with_dictionary_enabled(false);
+
+        // table wide encoding only applies to integer columns
+        let writer_conf = builder
+            .clone()
+            .with_encoding("delta_binary_packed".to_string())
+            .with_column_encoding("score".to_string(), "byte_stream_split".to_string())
+            .build();
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        writer.flush_and_close()?;
+        let reader = SerializedFileReader::new(File::open(&file)?)?;
+        let row_group = reader.metadata().row_group(0);
+        assert!(row_group.column(0).encodings().contains(&Encoding::DELTA_BINARY_PACKED));
+        assert!(!row_group.column(1).encodings().contains(&Encoding::DELTA_BINARY_PACKED));
+        assert!(row_group.column(2).encodings().contains(&Encoding::BYTE_STREAM_SPLIT));
+
+        // encodings of columns must be supported by their types
+        let writer_conf = builder
+            .clone()
+            .with_column_encoding("name".to_string(), "delta_binary_packed".to_string())
+            .build();
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        assert!(matches!(
+            SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime),
+            Err(DataFusionError::Plan(_))
+        ));
+        let writer_conf = builder.with_encoding("rle".to_string()).build();
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        assert!(matches!(
+            SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime),
+            Err(DataFusionError::Plan(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolling_file_name() {
+        assert_eq!(
+            rolling_file_name("s3://bucket/t/part-00000.parquet", 1),
+            "s3://bucket/t/part-00000-00001.parquet"
+        );
+        assert_eq!(rolling_file_name("/t.d/part", 2), "/t.d/part-00002");
+    }
+
     #[tokio::test]
     async fn test_s3_read_write() -> Result<()> {
         let common_conf_builder = LakeSoulIOConfigBuilder::new()
@@ -18,7 +18,12 @@
 #![feature(io_error_more)]
 
 pub mod lakesoul_reader;
+pub mod column_path;
 pub mod filter;
 pub mod lakesoul_writer;
 pub mod lakesoul_io_config;
+pub mod sorted_merge;
+pub mod transform;
+pub mod pruning;
+pub mod hash_bucket;
 pub use datafusion::arrow::error::Result;
\ No newline at end of file
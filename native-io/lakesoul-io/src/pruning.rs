@@ -0,0 +1,696 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Skipping row groups and pages of parquet files that could not match
+//! simple predicates of filters, by statistics, bloom filters and page indexes.
+
+use arrow::compute::cast;
+use arrow_schema::{DataType, Schema};
+use datafusion::error::Result;
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Operator};
+use datafusion::scalar::ScalarValue;
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::arrow_reader::{RowSelection, RowSelector};
+use parquet::basic::Type as PhysicalType;
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::page_index::index::{Index, PageIndex};
+use parquet::file::statistics::Statistics;
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use twox_hash::XxHash64;
+
+/// Numbers of row groups and pages skipped by a reader
+#[derive(Debug, Default)]
+pub struct PruningMetrics {
+    row_groups_pruned: AtomicUsize,
+    pages_pruned: AtomicUsize,
+}
+
+impl PruningMetrics {
+    pub fn row_groups_pruned(&self) -> usize {
+        self.row_groups_pruned.load(AtomicOrdering::Relaxed)
+    }
+
+    pub fn pages_pruned(&self) -> usize {
+        self.pages_pruned.load(AtomicOrdering::Relaxed)
+    }
+
+    fn add_row_groups_pruned(&self, n: usize) {
+        self.row_groups_pruned.fetch_add(n, AtomicOrdering::Relaxed);
+    }
+
+    fn add_pages_pruned(&self, n: usize) {
+        self.pages_pruned.fetch_add(n, AtomicOrdering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PredicateOp {
+    Eq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl PredicateOp {
+    // op of `literal op column` as `column op literal`
+    fn swap(self) -> Self {
+        match self {
+            PredicateOp::Lt => PredicateOp::Gt,
+            PredicateOp::LtEq => PredicateOp::GtEq,
+            PredicateOp::Gt => PredicateOp::Lt,
+            PredicateOp::GtEq => PredicateOp::LtEq,
+            PredicateOp::Eq => PredicateOp::Eq,
+        }
+    }
+}
+
+/// `column op value`, or `column in values` with op of `Eq`
+#[derive(Debug, Clone)]
+pub(crate) struct ColumnPredicate {
+    column: String,
+    op: PredicateOp,
+    values: Vec<ScalarValue>,
+}
+
+/// Predicates of top-level columns compared with literals in conjunctions of filters,
+/// rows not matching any of them don't match the filters
+pub(crate) fn column_predicates(filters: &[Expr]) -> Vec<ColumnPredicate> {
+    let mut predicates = vec![];
+    for filter in filters {
+        collect_predicates(filter, &mut predicates);
+    }
+    predicates
+}
+
+fn collect_predicates(expr: &Expr, predicates: &mut Vec<ColumnPredicate>) {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        }) => {
+            collect_predicates(left, predicates);
+            collect_predicates(right, predicates);
+        }
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let op = match op {
+                Operator::Eq => PredicateOp::Eq,
+                Operator::Lt => PredicateOp::Lt,
+                Operator::LtEq => PredicateOp::LtEq,
+                Operator::Gt => PredicateOp::Gt,
+                Operator::GtEq => PredicateOp::GtEq,
+                _ => return,
+            };
+            match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(value)) => push_predicate(predicates, &column.name, op, value),
+                (Expr::Literal(value), Expr::Column(column)) => {
+                    push_predicate(predicates, &column.name, op.swap(), value)
+                }
+                _ => {}
+            }
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } => {
+            if let Expr::Column(column) = expr.as_ref() {
+                let values = list
+                    .iter()
+                    .map(|v| match v {
+                        Expr::Literal(value) if !value.is_null() => Some(value.clone()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(values) = values {
+                    predicates.push(ColumnPredicate {
+                        column: column.name.clone(),
+                        op: PredicateOp::Eq,
+                        values,
+                    });
+                }
+            }
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => {
+            if let (Expr::Column(column), Expr::Literal(low), Expr::Literal(high)) =
+                (expr.as_ref(), low.as_ref(), high.as_ref())
+            {
+                push_predicate(predicates, &column.name, PredicateOp::GtEq, low);
+                push_predicate(predicates, &column.name, PredicateOp::LtEq, high);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn push_predicate(predicates: &mut Vec<ColumnPredicate>, column: &str, op: PredicateOp, value: &ScalarValue) {
+    // comparing with null never matches, which is left to filters
+    if !value.is_null() {
+        predicates.push(ColumnPredicate {
+            column: column.to_string(),
+            op,
+            values: vec![value.clone()],
+        });
+    }
+}
+
+// Values compared in the order of parquet statistics
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub(crate) enum StatValue {
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+}
+
+// A predicate bound to a leaf column of the file, with values in its physical order
+struct LeafPredicate {
+    leaf: usize,
+    physical_type: PhysicalType,
+    op: PredicateOp,
+    values: Vec<StatValue>,
+}
+
+impl LeafPredicate {
+    // whether a row group or page with min and max could have matching rows
+    fn may_match(&self, min: Option<StatValue>, max: Option<StatValue>) -> bool {
+        let (min, max) = match (min, max) {
+            (Some(min), Some(max)) => (min, max),
+            // all nulls, which never match comparisons
+            _ => return false,
+        };
+        self.values.iter().any(|v| {
+            let min_cmp = min.partial_cmp(v);
+            let max_cmp = max.partial_cmp(v);
+            match self.op {
+                PredicateOp::Eq => {
+                    !matches!(min_cmp, Some(Ordering::Greater)) && !matches!(max_cmp, Some(Ordering::Less))
+                }
+                PredicateOp::Lt => !matches!(min_cmp, Some(Ordering::Greater | Ordering::Equal)),
+                PredicateOp::LtEq => !matches!(min_cmp, Some(Ordering::Greater)),
+                PredicateOp::Gt => !matches!(max_cmp, Some(Ordering::Less | Ordering::Equal)),
+                PredicateOp::GtEq => !matches!(max_cmp, Some(Ordering::Less)),
+            }
+        })
+    }
+}
+
+/// Prunes row groups and pages of one parquet file
+pub(crate) struct FilePruner<'a> {
+    metadata: &'a ParquetMetaData,
+    predicates: Vec<LeafPredicate>,
+    metrics: &'a PruningMetrics,
+}
+
+impl<'a> FilePruner<'a> {
+    /// Bind predicates to top-level primitive columns of the file,
+    /// predicates whose values could not be represented in column types are ignored
+    pub(crate) fn new(
+        metadata: &'a ParquetMetaData,
+        file_schema: &Schema,
+        predicates: &[ColumnPredicate],
+        metrics: &'a PruningMetrics,
+    ) -> Self {
+        let parquet_schema = metadata.file_metadata().schema_descr();
+        let predicates = predicates
+            .iter()
+            .filter_map(|predicate| {
+                let field = file_schema.field_with_name(&predicate.column).ok()?;
+                let leaf = parquet_schema
+                    .columns()
+                    .iter()
+                    .position(|c| c.path().parts() == [predicate.column.clone()])?;
+                let values = predicate
+                    .values
+                    .iter()
+                    .map(|v| stat_value(v, field.data_type()))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(LeafPredicate {
+                    leaf,
+                    physical_type: parquet_schema.column(leaf).physical_type(),
+                    op: predicate.op,
+                    values,
+                })
+            })
+            .collect();
+        FilePruner {
+            metadata,
+            predicates,
+            metrics,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.predicates.is_empty()
+    }
+
+    /// Row groups whose min and max statistics may match all predicates
+    pub(crate) fn prune_by_statistics(&self, row_groups: Vec<usize>) -> Vec<usize> {
+        let before = row_groups.len();
+        let row_groups = row_groups
+            .into_iter()
+            .filter(|rg| {
+                let rg = self.metadata.row_group(*rg);
+                self.predicates.iter().all(|p| match rg.column(p.leaf).statistics() {
+                    Some(stats) if stats.has_min_max_set() => match statistics_min_max(stats) {
+                        Some((min, max)) => p.may_match(Some(min), Some(max)),
+                        None => true,
+                    },
+                    Some(stats) if stats.null_count() as i64 == rg.num_rows() => false,
+                    _ => true,
+                })
+            })
+            .collect::<Vec<_>>();
+        self.metrics.add_row_groups_pruned(before - row_groups.len());
+        row_groups
+    }
+
+    /// Row groups whose bloom filters may contain values of all equality predicates
+    pub(crate) async fn prune_by_bloom_filters(
+        &self,
+        store: &dyn ObjectStore,
+        meta: &ObjectMeta,
+        row_groups: Vec<usize>,
+    ) -> Result<Vec<usize>> {
+        let before = row_groups.len();
+        let mut kept = Vec::with_capacity(row_groups.len());
+        for rg in row_groups {
+            let mut may_match = true;
+            for p in self.predicates.iter().filter(|p| p.op == PredicateOp::Eq) {
+                let offset = match self.metadata.row_group(rg).column(p.leaf).bloom_filter_offset() {
+                    Some(offset) => offset as usize,
+                    None => continue,
+                };
+                let bitset = match read_bloom_filter(store, meta, offset).await? {
+                    Some(bitset) => bitset,
+                    None => continue,
+                };
+                let hashes = p
+                    .values
+                    .iter()
+                    .map(|v| plain_bytes(v, p.physical_type).map(|bytes| xxhash64(&bytes)))
+                    .collect::<Option<Vec<u64>>>();
+                if let Some(hashes) = hashes {
+                    if !hashes.iter().any(|hash| sbbf_check(&bitset, *hash)) {
+                        may_match = false;
+                        break;
+                    }
+                }
+            }
+            if may_match {
+                kept.push(rg);
+            }
+        }
+        self.metrics.add_row_groups_pruned(before - kept.len());
+        Ok(kept)
+    }
+
+    /// Select rows of pages whose page index may match all predicates in row groups,
+    /// row groups without any selected rows are removed.
+    /// Returns None if the file has no page index.
+    pub(crate) fn select_pages(&self, row_groups: Vec<usize>) -> Option<(Vec<usize>, RowSelection)> {
+        let column_index = self.metadata.page_indexes()?;
+        let offset_index = self.metadata.offset_indexes()?;
+        let mut kept_row_groups = vec![];
+        let mut selectors = vec![];
+        let mut pages_pruned = 0;
+        for rg in row_groups {
+            let num_rows = self.metadata.row_group(rg).num_rows() as usize;
+            let mut ranges = vec![0..num_rows];
+            for p in &self.predicates {
+                let (index, locations) = match (
+                    column_index.get(rg).and_then(|c| c.get(p.leaf)),
+                    offset_index.get(rg).and_then(|c| c.get(p.leaf)),
+                ) {
+                    (Some(index), Some(locations)) => (index, locations),
+                    _ => continue,
+                };
+                let pages = match page_min_max(index) {
+                    Some(pages) if pages.len() == locations.len() => pages,
+                    _ => continue,
+                };
+                let mut page_ranges = vec![];
+                for (i, (min, max)) in pages.into_iter().enumerate() {
+                    if p.may_match(min, max) {
+                        let start = locations[i].first_row_index as usize;
+                        let end = locations
+                            .get(i + 1)
+                            .map_or(num_rows, |next| next.first_row_index as usize);
+                        page_ranges.push(start..end);
+                    } else {
+                        pages_pruned += 1;
+                    }
+                }
+                ranges = intersect_ranges(&ranges, &page_ranges);
+            }
+            if ranges.is_empty() {
+                continue;
+            }
+            kept_row_groups.push(rg);
+            let mut pos = 0;
+            for range in ranges {
+                if range.start > pos {
+                    selectors.push(RowSelector::skip(range.start - pos));
+                }
+                selectors.push(RowSelector::select(range.end - range.start));
+                pos = range.end;
+            }
+            if num_rows > pos {
+                selectors.push(RowSelector::skip(num_rows - pos));
+            }
+        }
+        self.metrics.add_pages_pruned(pages_pruned);
+        Some((kept_row_groups, RowSelection::from(selectors)))
+    }
+
+    /// Number of row groups removed by page selection, counted as pruned
+    pub(crate) fn count_row_groups_pruned(&self, n: usize) {
+        self.metrics.add_row_groups_pruned(n);
+    }
+}
+
+// Value in the order of statistics of the column type, None if the value could
+// not be represented in the type exactly or the order is not supported
+fn stat_value(value: &ScalarValue, data_type: &DataType) -> Option<StatValue> {
+    let value = if &value.get_datatype() == data_type {
+        value.clone()
+    } else {
+        let casted = ScalarValue::try_from_array(&cast(&value.to_array(), data_type).ok()?, 0).ok()?;
+        // lossy casts change results of range predicates
+        let back = ScalarValue::try_from_array(&cast(&casted.to_array(), &value.get_datatype()).ok()?, 0).ok()?;
+        if &back != value || casted.is_null() {
+            return None;
+        }
+        casted
+    };
+    match value {
+        ScalarValue::Int8(Some(v)) => Some(StatValue::Int(v as i64)),
+        ScalarValue::Int16(Some(v)) => Some(StatValue::Int(v as i64)),
+        ScalarValue::Int32(Some(v)) | ScalarValue::Date32(Some(v)) => Some(StatValue::Int(v as i64)),
+        // dates of milliseconds and timestamps of seconds are converted by the parquet writer
+        ScalarValue::Int64(Some(v))
+        | ScalarValue::TimestampMillisecond(Some(v), _)
+        | ScalarValue::TimestampMicrosecond(Some(v), _)
+        | ScalarValue::TimestampNanosecond(Some(v), _) => Some(StatValue::Int(v)),
+        ScalarValue::Float32(Some(v)) if !v.is_nan() => Some(StatValue::Float(v as f64)),
+        ScalarValue::Float64(Some(v)) if !v.is_nan() => Some(StatValue::Float(v)),
+        ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)) => Some(StatValue::Bytes(v.into_bytes())),
+        ScalarValue::Binary(Some(v)) | ScalarValue::LargeBinary(Some(v)) => Some(StatValue::Bytes(v)),
+        _ => None,
+    }
+}
+
+// None if the order of statistics is unknown, which could not prune
+pub(crate) fn statistics_min_max(stats: &Statistics) -> Option<(StatValue, StatValue)> {
+    match stats {
+        Statistics::Int32(s) => Some((StatValue::Int(*s.min() as i64), StatValue::Int(*s.max() as i64))),
+        Statistics::Int64(s) => Some((StatValue::Int(*s.min()), StatValue::Int(*s.max()))),
+        Statistics::Float(s) => Some((StatValue::Float(*s.min() as f64), StatValue::Float(*s.max() as f64))),
+        Statistics::Double(s) => Some((StatValue::Float(*s.min()), StatValue::Float(*s.max()))),
+        Statistics::ByteArray(s) => Some((
+            StatValue::Bytes(s.min().data().to_vec()),
+            StatValue::Bytes(s.max().data().to_vec()),
+        )),
+        _ => None,
+    }
+}
+
+fn page_min_max(index: &Index) -> Option<Vec<(Option<StatValue>, Option<StatValue>)>> {
+    fn convert<T, F: Fn(&T) -> StatValue>(pages: &[PageIndex<T>], f: F) -> Vec<(Option<StatValue>, Option<StatValue>)> {
+        pages
+            .iter()
+            .map(|page| (page.min.as_ref().map(&f), page.max.as_ref().map(&f)))
+            .collect()
+    }
+    match index {
+        Index::INT32(index) => Some(convert(&index.indexes, |v| StatValue::Int(*v as i64))),
+        Index::INT64(index) => Some(convert(&index.indexes, |v| StatValue::Int(*v))),
+        Index::FLOAT(index) => Some(convert(&index.indexes, |v| StatValue::Float(*v as f64))),
+        Index::DOUBLE(index) => Some(convert(&index.indexes, |v| StatValue::Float(*v))),
+        Index::BYTE_ARRAY(index) => Some(convert(&index.indexes, |v| StatValue::Bytes(v.data().to_vec()))),
+        _ => None,
+    }
+}
+
+// rows in both sorted lists of disjoint ranges
+fn intersect_ranges(a: &[Range<usize>], b: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].start.max(b[j].start);
+        let end = a[i].end.min(b[j].end);
+        if start < end {
+            result.push(start..end);
+        }
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+// plain encoding of a value in the physical type, which is hashed by bloom filters
+fn plain_bytes(value: &StatValue, physical_type: PhysicalType) -> Option<Vec<u8>> {
+    match (value, physical_type) {
+        (StatValue::Int(v), PhysicalType::INT32) => Some((*v as i32).to_le_bytes().to_vec()),
+        (StatValue::Int(v), PhysicalType::INT64) => Some(v.to_le_bytes().to_vec()),
+        (StatValue::Float(v), PhysicalType::FLOAT) => Some((*v as f32).to_le_bytes().to_vec()),
+        (StatValue::Float(v), PhysicalType::DOUBLE) => Some(v.to_le_bytes().to_vec()),
+        (StatValue::Bytes(v), PhysicalType::BYTE_ARRAY) => Some(v.clone()),
+        _ => None,
+    }
+}
+
+fn xxhash64(bytes: &[u8]) -> u64 {
+    use std::hash::Hasher;
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+const SBBF_SALT: [u32; 8] = [
+    0x47b6137b, 0x44974d91, 0x8824ad5b, 0xa2b7289d, 0x705495c7, 0x2df1424b, 0x9efc4947, 0x5c6bfb31,
+];
+
+// Whether the split block bloom filter may contain a value of the hash, as specified by parquet
+fn sbbf_check(bitset: &[u8], hash: u64) -> bool {
+    let num_blocks = (bitset.len() / 32) as u64;
+    if num_blocks == 0 {
+        return true;
+    }
+    let block = ((((hash >> 32) * num_blocks) >> 32) as usize) * 32;
+    let key = hash as u32;
+    SBBF_SALT.iter().enumerate().all(|(i, salt)| {
+        let bit = key.wrapping_mul(*salt) >> 27;
+        let word = u32::from_le_bytes(bitset[block + i * 4..block + i * 4 + 4].try_into().unwrap());
+        word & (1 << bit) != 0
+    })
+}
+
+// The header is at most a few dozens of bytes
+const BLOOM_FILTER_HEADER_SIZE: usize = 64;
+
+// Bitset of the bloom filter at offset, None if the header is not recognized
+async fn read_bloom_filter(store: &dyn ObjectStore, meta: &ObjectMeta, offset: usize) -> Result<Option<Vec<u8>>> {
+    let header_end = (offset + BLOOM_FILTER_HEADER_SIZE).min(meta.size);
+    let header = store.get_range(&meta.location, offset..header_end).await?;
+    let (num_bytes, header_len) = match parse_bloom_filter_header(&header) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let start = offset + header_len;
+    let bitset = store.get_range(&meta.location, start..start + num_bytes).await?;
+    Ok(Some(bitset.to_vec()))
+}
+
+// numBytes and length of a BloomFilterHeader in thrift compact protocol
+fn parse_bloom_filter_header(buf: &[u8]) -> Option<(usize, usize)> {
+    let mut pos = 0;
+    let mut last_field_id = 0i64;
+    let mut num_bytes = None;
+    loop {
+        let byte = *buf.get(pos)?;
+        pos += 1;
+        let field_type = byte & 0x0f;
+        if field_type == 0 {
+            break;
+        }
+        let field_id = match byte >> 4 {
+            0 => {
+                let (v, n) = read_varint(&buf[pos..])?;
+                pos += n;
+                zigzag(v)
+            }
+            delta => last_field_id + delta as i64,
+        };
+        last_field_id = field_id;
+        if field_id == 1 && field_type == 5 {
+            let (v, n) = read_varint(&buf[pos..])?;
+            pos += n;
+            num_bytes = Some(zigzag(v) as usize);
+        } else {
+            pos += skip_thrift_value(&buf[pos..], field_type, false)?;
+        }
+    }
+    Some((num_bytes?, pos))
+}
+
+fn read_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn zigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+// length of a value of thrift compact type, booleans of fields are encoded in their types
+fn skip_thrift_value(buf: &[u8], value_type: u8, in_list: bool) -> Option<usize> {
+    match value_type {
+        1 | 2 => Some(if in_list { 1 } else { 0 }),
+        3 => Some(1),
+        4..=6 => read_varint(buf).map(|(_, n)| n),
+        7 => Some(8),
+        8 => {
+            let (len, n) = read_varint(buf)?;
+            Some(n + len as usize)
+        }
+        9 | 10 => {
+            let header = *buf.first()?;
+            let mut pos = 1;
+            let mut size = (header >> 4) as usize;
+            if size == 15 {
+                let (v, n) = read_varint(&buf[pos..])?;
+                pos += n;
+                size = v as usize;
+            }
+            for _ in 0..size {
+                pos += skip_thrift_value(buf.get(pos..)?, header & 0x0f, true)?;
+            }
+            Some(pos)
+        }
+        12 => {
+            let mut pos = 0;
+            loop {
+                let byte = *buf.get(pos)?;
+                pos += 1;
+                if byte & 0x0f == 0 {
+                    return Some(pos);
+                }
+                if byte >> 4 == 0 {
+                    pos += read_varint(buf.get(pos..)?)?.1;
+                }
+                pos += skip_thrift_value(buf.get(pos..)?, byte & 0x0f, false)?;
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::logical_expr::col;
+
+    fn sbbf_insert(bitset: &mut [u8], hash: u64) {
+        let num_blocks = (bitset.len() / 32) as u64;
+        let block = ((((hash >> 32) * num_blocks) >> 32) as usize) * 32;
+        let key = hash as u32;
+        for (i, salt) in SBBF_SALT.iter().enumerate() {
+            let bit = key.wrapping_mul(*salt) >> 27;
+            let pos = block + i * 4;
+            let word = u32::from_le_bytes(bitset[pos..pos + 4].try_into().unwrap()) | (1 << bit);
+            bitset[pos..pos + 4].copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_sbbf() {
+        let mut bitset = vec![0u8; 1024];
+        for v in 0..100i64 {
+            sbbf_insert(&mut bitset, xxhash64(&v.to_le_bytes()));
+        }
+        assert!((0..100i64).all(|v| sbbf_check(&bitset, xxhash64(&v.to_le_bytes()))));
+        let false_positives = (1000..2000i64)
+            .filter(|v| sbbf_check(&bitset, xxhash64(&v.to_le_bytes())))
+            .count();
+        assert!(false_positives < 50);
+    }
+
+    #[test]
+    fn test_parse_bloom_filter_header() {
+        // numBytes = 1024, algorithm = {BLOCK: {}}, hash = {XXHASH: {}}, compression = {UNCOMPRESSED: {}}
+        let header = [
+            0x15, 0x80, 0x10, 0x1c, 0x1c, 0x00, 0x00, 0x1c, 0x1c, 0x00, 0x00, 0x1c, 0x1c, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(parse_bloom_filter_header(&header), Some((1024, header.len())));
+        assert_eq!(parse_bloom_filter_header(&header[..3]), None);
+    }
+
+    #[test]
+    fn test_column_predicates() {
+        let filters = vec![
+            col("a")
+                .eq(Expr::Literal(ScalarValue::Int32(Some(1))))
+                .and(Expr::Literal(ScalarValue::Int32(Some(5))).gt(col("b"))),
+            col("c").in_list(vec![Expr::Literal(ScalarValue::Utf8(Some("x".to_string())))], false),
+            col("d").eq(col("e")).or(col("a").is_null()),
+        ];
+        let predicates = column_predicates(&filters);
+        assert_eq!(predicates.len(), 3);
+        assert_eq!(predicates[0].column, "a");
+        assert_eq!(predicates[0].op, PredicateOp::Eq);
+        assert_eq!(predicates[1].column, "b");
+        assert_eq!(predicates[1].op, PredicateOp::Lt);
+        assert_eq!(predicates[2].column, "c");
+        assert_eq!(predicates[2].op, PredicateOp::Eq);
+    }
+
+    #[test]
+    fn test_stat_value() {
+        assert_eq!(
+            stat_value(&ScalarValue::Int64(Some(3)), &DataType::Int32),
+            Some(StatValue::Int(3))
+        );
+        // 1.5 could not be compared with statistics of integers
+        assert_eq!(stat_value(&ScalarValue::Float64(Some(1.5)), &DataType::Int32), None);
+        assert_eq!(
+            stat_value(&ScalarValue::Utf8(Some("ab".to_string())), &DataType::Utf8),
+            Some(StatValue::Bytes(b"ab".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_intersect_ranges() {
+        assert_eq!(intersect_ranges(&[0..10, 20..30], &[5..25]), vec![5..10, 20..25]);
+        assert!(intersect_ranges(&[0..10], &[10..20]).is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use arrow::array::{new_null_array, Array, ArrayRef};
+use arrow::compute::kernels::interleave::interleave;
+use arrow_schema::DataType;
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+use datafusion_common::DataFusionError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Merge values of one column among all rows sharing the same primary key.
+/// This is the native counterpart of Spark's `MergeOperator`.
+pub trait MergeOperator: Send + Sync {
+    /// `arrays` are this column of all buffered batches, `rows_of_keys` contains
+    /// (array index, row index) of every row of each key, ordered from oldest to newest.
+    /// A key may have no rows for this column, in which case the merged value should be null.
+    /// Returns the merged array with one value per key.
+    fn merge(
+        &self,
+        data_type: &DataType,
+        arrays: &[ArrayRef],
+        rows_of_keys: &[Vec<(usize, usize)>],
+    ) -> Result<ArrayRef>;
+}
+
+/// Take the picked row of each key, or null for keys with no row picked
+fn take_picked_rows(data_type: &DataType, arrays: &[ArrayRef], picked: &[Option<(usize, usize)>]) -> Result<ArrayRef> {
+    let null_array = new_null_array(data_type, 1);
+    let mut sources = arrays.iter().map(|a| a.as_ref()).collect::<Vec<&dyn Array>>();
+    let null_idx = sources.len();
+    sources.push(null_array.as_ref());
+    let indices = picked
+        .iter()
+        .map(|p| p.unwrap_or((null_idx, 0)))
+        .collect::<Vec<(usize, usize)>>();
+    Ok(interleave(&sources, &indices)?)
+}
+
+/// Use value of the newest row, same as `DefaultMergeOp`
+#[derive(Debug, Default)]
+pub struct UseLast;
+
+impl MergeOperator for UseLast {
+    fn merge(
+        &self,
+        data_type: &DataType,
+        arrays: &[ArrayRef],
+        rows_of_keys: &[Vec<(usize, usize)>],
+    ) -> Result<ArrayRef> {
+        let picked = rows_of_keys.iter().map(|rows| rows.last().copied()).collect::<Vec<_>>();
+        take_picked_rows(data_type, arrays, &picked)
+    }
+}
+
+/// Use value of the newest row whose value is not null, same as `MergeNonNullOp`
+#[derive(Debug, Default)]
+pub struct NonNullLast;
+
+impl MergeOperator for NonNullLast {
+    fn merge(
+        &self,
+        data_type: &DataType,
+        arrays: &[ArrayRef],
+        rows_of_keys: &[Vec<(usize, usize)>],
+    ) -> Result<ArrayRef> {
+        let picked = rows_of_keys
+            .iter()
+            .map(|rows| {
+                rows.iter()
+                    .rev()
+                    .find(|(array_idx, row_idx)| arrays[*array_idx].is_valid(*row_idx))
+                    .copied()
+            })
+            .collect::<Vec<_>>();
+        take_picked_rows(data_type, arrays, &picked)
+    }
+}
+
+/// Sum up all non-null values of numeric columns, same as `MergeOpInt` and `MergeOpLong`
+#[derive(Debug, Default)]
+pub struct Sum;
+
+impl Sum {
+    fn add(acc: ScalarValue, value: ScalarValue) -> Result<ScalarValue> {
+        if value.is_null() {
+            return Ok(acc);
+        }
+        if acc.is_null() {
+            return Ok(value);
+        }
+        Ok(match (acc, value) {
+            (ScalarValue::Int8(Some(a)), ScalarValue::Int8(Some(b))) => ScalarValue::Int8(Some(a.wrapping_add(b))),
+            (ScalarValue::Int16(Some(a)), ScalarValue::Int16(Some(b))) => ScalarValue::Int16(Some(a.wrapping_add(b))),
+            (ScalarValue::Int32(Some(a)), ScalarValue::Int32(Some(b))) => ScalarValue::Int32(Some(a.wrapping_add(b))),
+            (ScalarValue::Int64(Some(a)), ScalarValue::Int64(Some(b))) => ScalarValue::Int64(Some(a.wrapping_add(b))),
+            (ScalarValue::UInt8(Some(a)), ScalarValue::UInt8(Some(b))) => ScalarValue::UInt8(Some(a.wrapping_add(b))),
+            (ScalarValue::UInt16(Some(a)), ScalarValue::UInt16(Some(b))) => {
+                ScalarValue::UInt16(Some(a.wrapping_add(b)))
+            }
+            (ScalarValue::UInt32(Some(a)), ScalarValue::UInt32(Some(b))) => {
+                ScalarValue::UInt32(Some(a.wrapping_add(b)))
+            }
+            (ScalarValue::UInt64(Some(a)), ScalarValue::UInt64(Some(b))) => {
+                ScalarValue::UInt64(Some(a.wrapping_add(b)))
+            }
+            (ScalarValue::Float32(Some(a)), ScalarValue::Float32(Some(b))) => ScalarValue::Float32(Some(a + b)),
+            (ScalarValue::Float64(Some(a)), ScalarValue::Float64(Some(b))) => ScalarValue::Float64(Some(a + b)),
+            (ScalarValue::Decimal128(Some(a), precision, scale), ScalarValue::Decimal128(Some(b), _, _)) => {
+                ScalarValue::Decimal128(Some(a.wrapping_add(b)), precision, scale)
+            }
+            (acc, _) => {
+                return Err(DataFusionError::Internal(format!(
+                    "Sum merge operator does not support type {:?}",
+                    acc.get_datatype()
+                )))
+            }
+        })
+    }
+}
+
+impl MergeOperator for Sum {
+    fn merge(
+        &self,
+        data_type: &DataType,
+        arrays: &[ArrayRef],
+        rows_of_keys: &[Vec<(usize, usize)>],
+    ) -> Result<ArrayRef> {
+        let values = rows_of_keys
+            .iter()
+            .map(|rows| {
+                rows.iter()
+                    .try_fold(ScalarValue::try_from(data_type)?, |acc, (array_idx, row_idx)| {
+                        Sum::add(acc, ScalarValue::try_from_array(&arrays[*array_idx], *row_idx)?)
+                    })
+            })
+            .collect::<Result<Vec<ScalarValue>>>()?;
+        ScalarValue::iter_to_array(values)
+    }
+}
+
+/// Join all non-null values of string columns with a delimiter, same as `MergeOpString`
+#[derive(Debug)]
+pub struct JoinWithDelimiter {
+    delimiter: String,
+}
+
+impl JoinWithDelimiter {
+    pub fn new(delimiter: &str) -> Self {
+        JoinWithDelimiter {
+            delimiter: delimiter.to_string(),
+        }
+    }
+}
+
+impl Default for JoinWithDelimiter {
+    fn default() -> Self {
+        JoinWithDelimiter::new(",")
+    }
+}
+
+impl MergeOperator for JoinWithDelimiter {
+    fn merge(
+        &self,
+        data_type: &DataType,
+        arrays: &[ArrayRef],
+        rows_of_keys: &[Vec<(usize, usize)>],
+    ) -> Result<ArrayRef> {
+        if !matches!(data_type, DataType::Utf8 | DataType::LargeUtf8) {
+            return Err(DataFusionError::Internal(format!(
+                "JoinWithDelimiter merge operator does not support type {:?}",
+                data_type
+            )));
+        }
+        let values = rows_of_keys
+            .iter()
+            .map(|rows| {
+                let mut parts = Vec::with_capacity(rows.len());
+                for (array_idx, row_idx) in rows {
+                    match ScalarValue::try_from_array(&arrays[*array_idx], *row_idx)? {
+                        ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)) => parts.push(s),
+                        _ => {}
+                    }
+                }
+                let joined = if parts.is_empty() {
+                    None
+                } else {
+                    Some(parts.join(self.delimiter.as_str()))
+                };
+                Ok(match data_type {
+                    DataType::LargeUtf8 => ScalarValue::LargeUtf8(joined),
+                    _ => ScalarValue::Utf8(joined),
+                })
+            })
+            .collect::<Result<Vec<ScalarValue>>>()?;
+        ScalarValue::iter_to_array(values)
+    }
+}
+
+/// Merge operators that could be referred by name in `LakeSoulIOConfig`.
+/// Built-in operators are always registered, custom ones could be added with `register`.
+#[derive(Clone)]
+pub struct MergeOperatorRegistry {
+    operators: HashMap<String, Arc<dyn MergeOperator>>,
+}
+
+impl Default for MergeOperatorRegistry {
+    fn default() -> Self {
+        let mut registry = MergeOperatorRegistry {
+            operators: HashMap::new(),
+        };
+        registry.register("UseLast".to_string(), Arc::new(UseLast));
+        registry.register("Sum".to_string(), Arc::new(Sum));
+        registry.register("NonNullLast".to_string(), Arc::new(NonNullLast));
+        registry.register("JoinWithDelimiter".to_string(), Arc::new(JoinWithDelimiter::default()));
+        registry
+    }
+}
+
+impl MergeOperatorRegistry {
+    pub fn register(&mut self, name: String, merge_operator: Arc<dyn MergeOperator>) {
+        self.operators.insert(name, merge_operator);
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn MergeOperator>> {
+        self.operators
+            .get(name)
+            .cloned()
+            .ok_or_else(|| DataFusionError::Internal(format!("unknown merge operator {}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+
+    #[test]
+    fn test_builtin_merge_operators() -> Result<()> {
+        let registry = MergeOperatorRegistry::default();
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(1), Some(2), None])),
+            Arc::new(Int64Array::from(vec![Some(10), None])),
+        ];
+        // key 0 has rows in both arrays, key 1 only has null values, key 2 has no row
+        let rows_of_keys = vec![vec![(0, 0), (1, 0), (0, 1)], vec![(0, 2), (1, 1)], vec![]];
+
+        let merged = registry
+            .get("UseLast")?
+            .merge(&DataType::Int64, &arrays, &rows_of_keys)?;
+        assert_eq!(merged.data(), Int64Array::from(vec![Some(2), None, None]).data());
+
+        let merged = registry.get("Sum")?.merge(&DataType::Int64, &arrays, &rows_of_keys)?;
+        assert_eq!(merged.data(), Int64Array::from(vec![Some(13), None, None]).data());
+
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![Some("a"), None])),
+            Arc::new(StringArray::from(vec![Some("b"), None])),
+        ];
+        let rows_of_keys = vec![vec![(0, 0), (1, 0), (0, 1)], vec![(1, 1)]];
+        let merged = registry
+            .get("NonNullLast")?
+            .merge(&DataType::Utf8, &arrays, &rows_of_keys)?;
+        assert_eq!(merged.data(), StringArray::from(vec![Some("b"), None]).data());
+
+        let merged = registry
+            .get("JoinWithDelimiter")?
+            .merge(&DataType::Utf8, &arrays, &rows_of_keys)?;
+        assert_eq!(merged.data(), StringArray::from(vec![Some("a,b"), None]).data());
+
+        assert!(registry.get("NoSuchOp").is_err());
+        Ok(())
+    }
+}
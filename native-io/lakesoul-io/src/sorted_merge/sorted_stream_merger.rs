@@ -0,0 +1,344 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::lakesoul_io_config::{CdcReadMode, LakeSoulIOConfig};
+use crate::lakesoul_reader::ArrowResult;
+use crate::sorted_merge::merge_operator::{MergeOperator, UseLast};
+use arrow::array::{as_string_array, Array, ArrayRef};
+use arrow::compute::cast;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{Row, RowConverter, Rows, SortField};
+use arrow_schema::{DataType, SchemaRef};
+use datafusion::error::Result;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion_common::DataFusionError;
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::JoinHandle;
+
+/// Change kind of deleted rows in CDC column
+pub const CDC_DELETE_KIND: &str = "delete";
+
+/// Merge streams that are each sorted by primary keys into one stream with one row per key.
+/// Streams must be given in file order, so that rows of each key are passed to
+/// `MergeOperator`s from oldest to newest. By default a row of a later stream overrides rows
+/// with the same key from earlier streams, and within one stream the last row wins.
+/// A file written by partial upsert may only contain some of the columns, rows of such file
+/// do not take part in merging of absent columns so that older values are kept. Keys only
+/// written by such files get the values filled by the reader, e.g. default column values.
+/// For CDC tables, keys whose newest row is a delete are dropped, or in changelog mode
+/// all rows are emitted without merging.
+/// Merging runs in a spawned task and sends merged batches through a channel,
+/// so that it could be wrapped by `ReceiverStreamExec`.
+pub struct SortedStreamMerger {
+    schema: SchemaRef,
+    streams: Vec<SendableRecordBatchStream>,
+    // whether each column in schema exists in the file of each stream
+    column_exists: Vec<Vec<bool>>,
+    // indices of primary key columns in schema
+    pk_indices: Vec<usize>,
+    // merge operator of each column in schema
+    merge_operators: Vec<Arc<dyn MergeOperator>>,
+    // index of CDC column in schema and how to read it
+    cdc: Option<(usize, CdcReadMode)>,
+    batch_size: usize,
+}
+
+/// Current position of one input stream
+struct MergeCursor {
+    stream: SendableRecordBatchStream,
+    stream_idx: usize,
+    batch: Option<RecordBatch>,
+    // primary key columns of current batch in row format
+    rows: Option<Rows>,
+    row_idx: usize,
+    // index of current batch in the merger's buffered batches once any of its rows is referenced
+    buffered_idx: Option<usize>,
+}
+
+impl MergeCursor {
+    fn new(stream: SendableRecordBatchStream, stream_idx: usize) -> Self {
+        MergeCursor {
+            stream,
+            stream_idx,
+            batch: None,
+            rows: None,
+            row_idx: 0,
+            buffered_idx: None,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.batch.is_none()
+    }
+
+    fn current_row(&self) -> Row<'_> {
+        self.rows.as_ref().unwrap().row(self.row_idx)
+    }
+
+    // convert key of current row separately, since current batch may be replaced
+    // before we finish collecting all rows of this key
+    fn current_key(&self, converter: &mut RowConverter, pk_indices: &[usize]) -> Result<Rows> {
+        let batch = self.batch.as_ref().unwrap();
+        let columns = pk_indices
+            .iter()
+            .map(|i| batch.column(*i).slice(self.row_idx, 1))
+            .collect::<Vec<ArrayRef>>();
+        Ok(converter.convert_columns(&columns)?)
+    }
+
+    fn buffer_current_batch(&mut self, buffered: &mut Vec<(usize, RecordBatch)>) -> usize {
+        match self.buffered_idx {
+            Some(idx) => idx,
+            None => {
+                buffered.push((self.stream_idx, self.batch.clone().unwrap()));
+                let idx = buffered.len() - 1;
+                self.buffered_idx = Some(idx);
+                idx
+            }
+        }
+    }
+
+    async fn advance(&mut self, converter: &mut RowConverter, pk_indices: &[usize]) -> Result<()> {
+        self.row_idx += 1;
+        if self.row_idx >= self.batch.as_ref().map_or(0, |b| b.num_rows()) {
+            self.next_batch(converter, pk_indices).await?;
+        }
+        Ok(())
+    }
+
+    async fn next_batch(&mut self, converter: &mut RowConverter, pk_indices: &[usize]) -> Result<()> {
+        self.batch = None;
+        self.rows = None;
+        self.row_idx = 0;
+        self.buffered_idx = None;
+        while let Some(batch) = self.stream.next().await {
+            let batch = batch?;
+            if batch.num_rows() > 0 {
+                let columns = pk_indices
+                    .iter()
+                    .map(|i| batch.column(*i).clone())
+                    .collect::<Vec<ArrayRef>>();
+                self.rows = Some(converter.convert_columns(&columns)?);
+                self.batch = Some(batch);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SortedStreamMerger {
+    /// `existing_columns` are names of columns written in the file of each stream,
+    /// other columns were left out by partial upsert and filled by the reader.
+    pub fn try_new(
+        streams: Vec<SendableRecordBatchStream>,
+        existing_columns: Vec<Vec<String>>,
+        schema: SchemaRef,
+        config: &LakeSoulIOConfig,
+    ) -> Result<Self> {
+        let column_exists = existing_columns
+            .iter()
+            .map(|cols| schema.fields().iter().map(|f| cols.contains(f.name())).collect())
+            .collect::<Vec<Vec<bool>>>();
+        let pk_indices = config
+            .primary_keys
+            .iter()
+            .map(|pk| schema.index_of(pk.as_str()).map_err(DataFusionError::ArrowError))
+            .collect::<Result<Vec<usize>>>()?;
+        let merge_operators = schema
+            .fields()
+            .iter()
+            .map(|f| match config.merge_operators.get(f.name()) {
+                // primary key columns are equal among rows of a key
+                Some(merge_op) if !config.primary_keys.contains(f.name()) => {
+                    config.merge_operator_registry.get(merge_op.as_str())
+                }
+                _ => Ok(Arc::new(UseLast) as Arc<dyn MergeOperator>),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cdc = match &config.cdc_column {
+            Some(cdc_column) => Some((
+                schema
+                    .index_of(cdc_column.as_str())
+                    .map_err(DataFusionError::ArrowError)?,
+                config.cdc_read_mode,
+            )),
+            None => None,
+        };
+        Ok(SortedStreamMerger {
+            schema,
+            streams,
+            column_exists,
+            pk_indices,
+            merge_operators,
+            cdc,
+            batch_size: config.batch_size,
+        })
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Spawn the merging task on current tokio runtime
+    pub fn start(self) -> (Receiver<ArrowResult<RecordBatch>>, JoinHandle<()>) {
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let join_handle = tokio::task::spawn(async move {
+            if let Err(e) = self.merge(&tx).await {
+                let _ = tx.send(Err(e.into())).await;
+            }
+        });
+        (rx, join_handle)
+    }
+
+    async fn merge(self, tx: &Sender<ArrowResult<RecordBatch>>) -> Result<()> {
+        let SortedStreamMerger {
+            schema,
+            streams,
+            column_exists,
+            pk_indices,
+            merge_operators,
+            cdc,
+            batch_size,
+        } = self;
+        let sort_fields = pk_indices
+            .iter()
+            .map(|i| SortField::new(schema.field(*i).data_type().clone()))
+            .collect::<Vec<SortField>>();
+        let mut converter = RowConverter::new(sort_fields)?;
+
+        let mut cursors = Vec::with_capacity(streams.len());
+        for (stream_idx, stream) in streams.into_iter().enumerate() {
+            let mut cursor = MergeCursor::new(stream, stream_idx);
+            cursor.next_batch(&mut converter, &pk_indices).await?;
+            cursors.push(cursor);
+        }
+
+        // (stream index, batch) of batches referenced by keys not yet emitted
+        let mut buffered: Vec<(usize, RecordBatch)> = vec![];
+        // for each merged key, (buffered batch index, row index) of all its rows from oldest to newest
+        let mut merged_keys: Vec<Vec<(usize, usize)>> = Vec::with_capacity(batch_size);
+        // keys collected since batches were last released, including deleted ones not merged,
+        // so that batches only holding deleted keys are not buffered without bound
+        let mut collected_keys = 0;
+        loop {
+            let min_cursor = cursors
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| !c.is_finished())
+                .min_by(|(_, a), (_, b)| a.current_row().cmp(&b.current_row()))
+                .map(|(idx, _)| idx);
+            let min_cursor = match min_cursor {
+                Some(idx) => idx,
+                None => break,
+            };
+
+            let key = cursors[min_cursor].current_key(&mut converter, &pk_indices)?;
+            let mut rows_of_key = vec![];
+            for cursor in cursors.iter_mut() {
+                while !cursor.is_finished() && cursor.current_row() == key.row(0) {
+                    let batch_idx = cursor.buffer_current_batch(&mut buffered);
+                    rows_of_key.push((batch_idx, cursor.row_idx));
+                    cursor.advance(&mut converter, &pk_indices).await?;
+                }
+            }
+            match cdc {
+                Some((_, CdcReadMode::Changelog)) => merged_keys.extend(rows_of_key.into_iter().map(|row| vec![row])),
+                Some((cdc_idx, CdcReadMode::Merged)) if is_deleted(&buffered, cdc_idx, &rows_of_key)? => {}
+                _ => merged_keys.push(rows_of_key),
+            }
+            collected_keys += 1;
+
+            if merged_keys.len() >= batch_size || collected_keys >= batch_size {
+                if !merged_keys.is_empty() {
+                    let batch = build_merged_batch(&schema, &merge_operators, &column_exists, &buffered, &merged_keys)?;
+                    if tx.send(Ok(batch)).await.is_err() {
+                        // reader has been dropped
+                        return Ok(());
+                    }
+                }
+                buffered.clear();
+                merged_keys.clear();
+                collected_keys = 0;
+                cursors.iter_mut().for_each(|c| c.buffered_idx = None);
+            }
+        }
+        if !merged_keys.is_empty() {
+            let batch = build_merged_batch(&schema, &merge_operators, &column_exists, &buffered, &merged_keys)?;
+            let _ = tx.send(Ok(batch)).await;
+        }
+        Ok(())
+    }
+}
+
+// whether the newest row of a key is a delete, the CDC column could be of any string
+// type like large or dictionary encoded strings
+fn is_deleted(buffered: &[(usize, RecordBatch)], cdc_idx: usize, rows_of_key: &[(usize, usize)]) -> Result<bool> {
+    let (batch_idx, row_idx) = *rows_of_key.last().unwrap();
+    let kind = buffered[batch_idx].1.column(cdc_idx).slice(row_idx, 1);
+    let kind = cast(&kind, &DataType::Utf8)
+        .map_err(|e| DataFusionError::Internal(format!("CDC column should be of string type: {}", e)))?;
+    let kind = as_string_array(&kind);
+    Ok(kind.is_valid(0) && kind.value(0) == CDC_DELETE_KIND)
+}
+
+fn build_merged_batch(
+    schema: &SchemaRef,
+    merge_operators: &[Arc<dyn MergeOperator>],
+    column_exists: &[Vec<bool>],
+    buffered: &[(usize, RecordBatch)],
+    merged_keys: &[Vec<(usize, usize)>],
+) -> Result<RecordBatch> {
+    let columns = merge_operators
+        .iter()
+        .enumerate()
+        .map(|(col_idx, merge_operator)| {
+            let data_type = schema.field(col_idx).data_type();
+            let arrays = buffered
+                .iter()
+                .map(|(_, batch)| batch.column(col_idx).clone())
+                .collect::<Vec<ArrayRef>>();
+            if buffered
+                .iter()
+                .all(|(stream_idx, _)| column_exists[*stream_idx][col_idx])
+            {
+                merge_operator.merge(data_type, &arrays, merged_keys)
+            } else {
+                // rows from files without this column should not override older values,
+                // unless no row of the key has it, then values filled by the reader are merged
+                let rows_of_keys = merged_keys
+                    .iter()
+                    .map(|rows| {
+                        let existing = rows
+                            .iter()
+                            .filter(|(batch_idx, _)| column_exists[buffered[*batch_idx].0][col_idx])
+                            .copied()
+                            .collect::<Vec<(usize, usize)>>();
+                        if existing.is_empty() {
+                            rows.clone()
+                        } else {
+                            existing
+                        }
+                    })
+                    .collect::<Vec<Vec<(usize, usize)>>>();
+                merge_operator.merge(data_type, &arrays, &rows_of_keys)
+            }
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
@@ -0,0 +1,209 @@
+/*
+ * Copyright [2022] [DMetaSoul Team]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use arrow::array::{new_null_array, Array, ArrayRef, StructArray};
+use arrow::compute::cast;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+use datafusion_common::DataFusionError;
+use std::collections::HashMap;
+
+/// Whether values of `from` type could be read as `to` type without losing information.
+/// This is the set of type changes allowed by schema evolution.
+pub fn can_widen(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (Int8, Int16 | Int32 | Int64 | Float64)
+        | (Int16, Int32 | Int64 | Float64)
+        | (Int32, Int64 | Float64)
+        | (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+        | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+        | (UInt32, UInt64 | Int64)
+        | (Float32, Float64)
+        | (Utf8, LargeUtf8)
+        | (Binary, LargeBinary) => true,
+        // Spark reads timestamps of any unit or time zone as microseconds in the session
+        // time zone, like INT96 columns read as nanoseconds, so they are converted by cast
+        (Timestamp(_, _), Timestamp(_, _)) => true,
+        (Decimal128(from_precision, from_scale), Decimal128(to_precision, to_scale)) => {
+            // both integral and fractional digits must not shrink
+            to_scale >= from_scale
+                && *to_precision as i16 - *to_scale as i16 >= *from_precision as i16 - *from_scale as i16
+        }
+        // fields are matched by name, missing fields are read as nulls
+        (Struct(from_fields), Struct(to_fields)) => to_fields.iter().all(|to_field| {
+            from_fields
+                .iter()
+                .find(|f| f.name() == to_field.name())
+                .map_or(true, |f| can_widen(f.data_type(), to_field.data_type()))
+        }),
+        _ => false,
+    }
+}
+
+/// Check that every column of `file_schema` that appears in `target_schema`
+/// could be widened to the target type
+pub fn check_schema_compatible(file_schema: &Schema, target_schema: &Schema) -> Result<()> {
+    for target_field in target_schema.fields() {
+        if let Ok(file_field) = file_schema.field_with_name(target_field.name()) {
+            if !can_widen(file_field.data_type(), target_field.data_type()) {
+                return Err(DataFusionError::Internal(format!(
+                    "column {} of type {:?} cannot be read as {:?}",
+                    target_field.name(),
+                    file_field.data_type(),
+                    target_field.data_type()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adapt a batch read from one file to the target schema: columns are reordered by name,
+/// widened to target types, and missing columns are filled with default values or nulls.
+pub fn uniform_record_batch(
+    batch: RecordBatch,
+    target_schema: &SchemaRef,
+    default_values: &HashMap<String, ScalarValue>,
+) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let batch_schema = batch.schema();
+    let columns = target_schema
+        .fields()
+        .iter()
+        .map(|field| match batch_schema.column_with_name(field.name()) {
+            Some((idx, _)) => uniform_array(batch.column(idx), field.data_type()),
+            None => match default_values.get(field.name()) {
+                Some(value) => Ok(value.to_array_of_size(num_rows)),
+                None => Ok(new_null_array(field.data_type(), num_rows)),
+            },
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        target_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?)
+}
+
+// Widen an array to the target type, struct fields are matched by name recursively
+fn uniform_array(array: &ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    match (array.data_type(), data_type) {
+        (from, to) if from == to => Ok(array.clone()),
+        (DataType::Struct(_), DataType::Struct(to_fields)) => {
+            let struct_array = array
+                .as_any()
+                .downcast_ref::<StructArray>()
+                .ok_or_else(|| DataFusionError::Internal("expect a struct array".to_string()))?;
+            let children = to_fields
+                .iter()
+                .map(|field| match struct_array.column_by_name(field.name()) {
+                    Some(child) => Ok((field.clone(), uniform_array(child, field.data_type())?)),
+                    None => Ok((field.clone(), new_null_array(field.data_type(), array.len()))),
+                })
+                .collect::<Result<Vec<(Field, ArrayRef)>>>()?;
+            let struct_array = match array.data().null_buffer() {
+                Some(nulls) => StructArray::from((children, nulls.clone())),
+                None => StructArray::from(children),
+            };
+            Ok(std::sync::Arc::new(struct_array))
+        }
+        _ => Ok(cast(array, data_type)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int32Array, Int64Array, StringArray};
+    use arrow_schema::TimeUnit;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_can_widen() {
+        assert!(can_widen(&DataType::Int32, &DataType::Int64));
+        assert!(can_widen(&DataType::Float32, &DataType::Float64));
+        assert!(can_widen(&DataType::Decimal128(10, 2), &DataType::Decimal128(12, 4)));
+        assert!(!can_widen(&DataType::Decimal128(10, 2), &DataType::Decimal128(10, 4)));
+        assert!(!can_widen(&DataType::Int64, &DataType::Int32));
+        assert!(!can_widen(&DataType::Utf8, &DataType::Int32));
+        assert!(can_widen(
+            &DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".to_string())),
+            &DataType::Timestamp(TimeUnit::Microsecond, None)
+        ));
+        assert!(can_widen(
+            &DataType::Struct(vec![Field::new("x", DataType::Int32, true)]),
+            &DataType::Struct(vec![
+                Field::new("y", DataType::Utf8, true),
+                Field::new("x", DataType::Int64, true)
+            ])
+        ));
+    }
+
+    #[test]
+    fn test_uniform_record_batch() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([
+            ("b", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            ("a", Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef),
+        ])?;
+        let target_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("c", DataType::Float64, true),
+            Field::new("d", DataType::Utf8, true),
+        ]));
+        let default_values = HashMap::from([("c".to_string(), ScalarValue::Float64(Some(1.5)))]);
+        let actual = uniform_record_batch(batch, &target_schema, &default_values)?;
+
+        assert_eq!(actual.schema(), target_schema);
+        assert_eq!(actual.column(0).data(), StringArray::from(vec!["x", "y"]).data());
+        assert_eq!(actual.column(1).data(), Int64Array::from(vec![1, 2]).data());
+        assert_eq!(actual.column(2).data(), Float64Array::from(vec![1.5, 1.5]).data());
+        assert_eq!(actual.column(3).null_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_uniform_struct_array() -> Result<()> {
+        let struct_array = StructArray::from(vec![
+            (
+                Field::new("y", DataType::Utf8, true),
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ),
+            (
+                Field::new("x", DataType::Int32, true),
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+            ),
+        ]);
+        let batch = RecordBatch::try_from_iter([("s", Arc::new(struct_array) as ArrayRef)])?;
+        let target_type = DataType::Struct(vec![
+            Field::new("x", DataType::Int64, true),
+            Field::new("z", DataType::Float64, true),
+        ]);
+        let target_schema = Arc::new(Schema::new(vec![Field::new("s", target_type, true)]));
+        let actual = uniform_record_batch(batch, &target_schema, &HashMap::new())?;
+
+        let actual = actual.column(0).as_any().downcast_ref::<StructArray>().unwrap();
+        assert_eq!(actual.column(0).data(), Int64Array::from(vec![1, 2]).data());
+        assert_eq!(actual.column(1).null_count(), 2);
+        Ok(())
+    }
+}